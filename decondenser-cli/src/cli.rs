@@ -1,4 +1,4 @@
-use crate::config::Config;
+use crate::config::{self, ResolvedConfig};
 use crate::{Files, Result};
 use anyhow::Context;
 use clap::{arg, value_parser};
@@ -29,44 +29,75 @@ fn cli() -> clap::Command {
             .subcommand_required(true)
             .subcommand(format_cli())
             .subcommand(unescape_cli())
+            .subcommand(config_cli())
     })
 }
 
 fn format_cli() -> clap::Command {
     command("fmt", |cmd| {
-        cmd
-        .about("Format text based on brackets nesting")
-        .next_help_heading("Main")
-        .args([
-            arg!(--input <INPUT> "File path or - for stdin").default_value("-"),
-            arg!(--output <OUTPUT> "File path or - for stdout").default_value("-"),
-            arg!(
-                --config <CONFIG>
-                "Path to the config file [default: decondenser.yml in this or parent directories]"
-            )
-            .value_parser(value_parser!(PathBuf))
-            .required(false),
-        ])
-        .next_help_heading("Formatting")
-        .args([
-            arg!(
-                --indent <INDENT>
-                "Number of spaces or string to use for indentation [default: 4]"
-            ),
-            arg!(
-                --"max-line-size" <SIZE>
-                "Best-effort max size of a line to fit into; see also --no-break-size \
-                [default: 80]"
-            ),
-            arg!(
-                --"no-break-size" <SIZE>
-                "Lines shorter than this (ignoring indent) won't be broken \
-                [default: --max-line-size / 2]"
-            ),
-        ])
+        cmd.about("Format text based on brackets nesting")
+            .next_help_heading("Main")
+            .args([
+                arg!(--input <INPUT> "File path or - for stdin").default_value("-"),
+                arg!(--output <OUTPUT> "File path or - for stdout").default_value("-"),
+            ])
+            .args(config_args())
+            .arg(arg!(
+                --verify
+                "Format the output once again and fail if it changes, reporting the first \
+                diverging line"
+            ))
+            .next_help_heading("Formatting")
+            .args(formatting_args())
     })
 }
 
+fn config_args() -> [clap::Arg; 2] {
+    [
+        arg!(
+            --config <CONFIG>
+            "Path to the YAML, TOML or JSON config file [default: decondenser.yml, \
+            decondenser.toml, .decondenser.json, or the [tool.decondenser] table in \
+            pyproject.toml, or the [package.metadata.decondenser] table in Cargo.toml in \
+            the directory of the input file or the current directory, or their parents]"
+        )
+        .value_parser(value_parser!(PathBuf))
+        .required(false),
+        arg!(
+            --profile <PROFILE>
+            "Name of the profile in the config's overrides to apply. Useful for stdin input, \
+            that can't be matched by the file globs"
+        ),
+    ]
+}
+
+/// Args that take priority over the config file
+fn formatting_args() -> [clap::Arg; 4] {
+    [
+        arg!(
+            --indent <INDENT>
+            "Number of spaces or string to use for indentation [default: 4]"
+        ),
+        arg!(
+            --"tab-width" <SIZE>
+            "Number of columns between tab stops used to measure tabs [default: 4]"
+        )
+        .value_parser(value_parser!(usize)),
+        arg!(
+            --"max-line-size" <SIZE>
+            "Best-effort max size of a line to fit into; see also --no-break-size \
+            [default: 80]"
+        )
+        .value_parser(value_parser!(usize)),
+        arg!(
+            --"no-break-size" <SIZE>
+            "Lines shorter than this (ignoring indent) won't be broken \
+            [default: --max-line-size / 2]"
+        )
+        .value_parser(value_parser!(usize)),
+    ]
+}
+
 fn unescape_cli() -> clap::Command {
     command("unescape", |cmd| {
         cmd.about(
@@ -77,6 +108,51 @@ fn unescape_cli() -> clap::Command {
     })
 }
 
+fn config_cli() -> clap::Command {
+    command("config", |cmd| {
+        cmd.about("Inspect the config file")
+            .subcommand_required(true)
+            .subcommand(command("show", |cmd| {
+                cmd.about(
+                    "Print the resolved config with the presets, extended files, overrides and \
+                    CLI args applied",
+                )
+                .next_help_heading("Main")
+                .arg(arg!(
+                    --input <INPUT>
+                    "Path of the input file to resolve the config for. The file isn't read"
+                ))
+                .args(config_args())
+                .next_help_heading("Formatting")
+                .args(formatting_args())
+            }))
+            .subcommand(command("validate", |cmd| {
+                cmd.about("Check the config file and the files it extends for errors")
+                    .arg(
+                        arg!(
+                            [FILE]
+                            "Path to the config file [default: the config file discovered in \
+                            the current directory or its parents]"
+                        )
+                        .value_parser(value_parser!(PathBuf)),
+                    )
+            }))
+            .subcommand(command("init", |cmd| {
+                cmd.about("Write a commented starter config file").args([
+                    arg!(--preset <PRESET> "Preset the config extends")
+                        .value_parser(["generic", "empty"])
+                        .default_value("generic"),
+                    arg!(--output <OUTPUT> "File path or - for stdout")
+                        .default_value("decondenser.yml"),
+                    arg!(--force "Overwrite the output file if it exists"),
+                ])
+            }))
+            .subcommand(command("schema", |cmd| {
+                cmd.about("Print the JSON Schema of the config file")
+            }))
+    })
+}
+
 fn command(name: &'static str, configure: fn(clap::Command) -> clap::Command) -> clap::Command {
     let command = clap::Command::new(name).long_about(None);
     configure(command)
@@ -90,10 +166,78 @@ pub(crate) fn run(files: &mut Files) -> Result {
     match subcommand.as_str() {
         "unescape" => unescape(cli),
         "fmt" => format(cli, files),
+        "config" => config(cli, files),
         _ => unreachable!("Unhandled subcommand: {subcommand}"),
     }
 }
 
+fn config(mut cli: clap::ArgMatches, files: &mut Files) -> Result {
+    let (subcommand, cli) = cli.remove_subcommand().unwrap();
+
+    match subcommand.as_str() {
+        "show" => config_show(cli, files),
+        "validate" => config_validate(cli, files),
+        "init" => config_init(cli),
+        "schema" => write_output("-", config::SCHEMA.trim_end()),
+        _ => unreachable!("Unhandled config subcommand: {subcommand}"),
+    }
+}
+
+fn config_show(mut cli: clap::ArgMatches, files: &mut Files) -> Result {
+    let input = cli.remove_one::<String>("input");
+    let input = input.as_deref().map(Path::new);
+
+    let (decondenser, config_files) = resolve(&mut cli, input, files)?;
+
+    let mut output = match config_files.as_slice() {
+        [] => "# No config file was found, so the defaults are used\n".to_owned(),
+        config_files => {
+            let config_files = config_files
+                .iter()
+                .map(|path| format!("#   {}", path.display()))
+                .collect::<Vec<_>>()
+                .join("\n");
+
+            format!("# Resolved from the config files:\n{config_files}\n")
+        }
+    };
+
+    output.push_str(&config::to_yaml(decondenser.to_config()));
+
+    write_output("-", &output)
+}
+
+fn config_validate(mut cli: clap::ArgMatches, files: &mut Files) -> Result {
+    let path = cli.remove_one::<PathBuf>("FILE");
+
+    let config = find_config(path.as_deref(), None, files)?.context(
+        "No config file was found in the current directory or its parents. \
+        Use `decondenser config init` to create one",
+    )?;
+
+    let config_files = config
+        .files()
+        .map(|path| format!("  {}", path.display()))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    write_output("-", &format!("Config is valid:\n{config_files}"))
+}
+
+fn config_init(mut cli: clap::ArgMatches) -> Result {
+    let preset = cli.remove_one::<String>("preset").unwrap();
+    let output = cli.remove_one::<String>("output").unwrap();
+    let force = cli.get_flag("force");
+
+    if output != "-" && !force && Path::new(&output).exists() {
+        return Err(
+            anyhow::anyhow!("File '{output}' already exists. Use --force to overwrite it").into(),
+        );
+    }
+
+    write_output(&output, config::starter(&preset).trim_end())
+}
+
 fn unescape(mut cli: clap::ArgMatches) -> Result {
     let input = cli.remove_one::<String>("input").unwrap();
     let output = cli.remove_one::<String>("output").unwrap();
@@ -107,14 +251,51 @@ fn unescape(mut cli: clap::ArgMatches) -> Result {
 fn format(mut cli: clap::ArgMatches, files: &mut Files) -> Result {
     let input = cli.remove_one::<String>("input").unwrap();
     let output = cli.remove_one::<String>("output").unwrap();
+    let verify = cli.get_flag("verify");
+
+    let input_path = Some(Path::new(&input)).filter(|_| input != "-");
+
+    let (decondenser, _) = resolve(&mut cli, input_path, files)?;
+
+    let input = read_input(&input)?;
+
+    let output_str = if verify {
+        decondenser.verify(&input).map_err(anyhow::Error::from)?
+    } else {
+        decondenser.format(&input)
+    };
+
+    write_output(&output, &output_str)
+}
+
+/// Resolves the config for the given input file, and applies the CLI args on
+/// top of it. Also returns the paths of the applied config files.
+fn resolve(
+    cli: &mut clap::ArgMatches,
+    input: Option<&Path>,
+    files: &mut Files,
+) -> Result<(decondenser::Decondenser, Vec<PathBuf>)> {
     let config = cli.remove_one::<PathBuf>("config");
+    let profile = cli.remove_one::<String>("profile");
     let indent = cli.remove_one::<String>("indent");
+    let tab_width = cli.remove_one::<usize>("tab-width");
     let max_line_size = cli.remove_one::<usize>("max-line-size");
     let no_break_size = cli.remove_one::<usize>("no-break-size");
 
-    let config = config_or_default(config.as_deref(), files)?;
+    let config = find_config(config.as_deref(), input, files)?.unwrap_or_default();
 
-    let mut decondenser = config.into_decondenser();
+    if let Some(profile) = &profile {
+        if !config.profiles().any(|defined| defined == profile) {
+            return Err(anyhow::anyhow!(
+                "Profile '{profile}' is not defined in the overrides of the config"
+            )
+            .into());
+        }
+    }
+
+    let config_files = config.files().map(Path::to_owned).collect();
+
+    let mut decondenser = config.into_decondenser(input, profile.as_deref());
 
     if let Some(indent) = indent {
         decondenser = match indent.parse::<usize>() {
@@ -123,6 +304,10 @@ fn format(mut cli: clap::ArgMatches, files: &mut Files) -> Result {
         };
     }
 
+    if let Some(tab_width) = tab_width {
+        decondenser = decondenser.tab_width(tab_width);
+    }
+
     if let Some(max_line_size) = max_line_size {
         decondenser = decondenser.max_line_size(max_line_size);
     }
@@ -131,22 +316,32 @@ fn format(mut cli: clap::ArgMatches, files: &mut Files) -> Result {
         decondenser = decondenser.no_break_size(no_break_size);
     }
 
-    let input = read_input(&input)?;
-    let output_str = decondenser.format(&input);
-
-    write_output(&output, &output_str)
+    Ok((decondenser, config_files))
 }
 
-fn config_or_default(config: Option<&Path>, files: &mut Files) -> Result<Config> {
-    Ok(match config {
-        Some(config) => Config::from_file(files, config)?.with_context(|| {
-            format!(
-                "Config file was not found at the specified path: '{}'",
-                config.display()
-            )
-        })?,
-        None => Config::discover(files)?.unwrap_or_default(),
-    })
+/// Reads the config file at the given path, or discovers it relative to the
+/// input file if there is one, or the current directory otherwise.
+fn find_config(
+    config: Option<&Path>,
+    input: Option<&Path>,
+    files: &mut Files,
+) -> Result<Option<ResolvedConfig>> {
+    let discovery_dir = input
+        .and_then(Path::parent)
+        .filter(|dir| !dir.as_os_str().is_empty());
+
+    let Some(config) = config else {
+        return ResolvedConfig::discover(files, discovery_dir);
+    };
+
+    let config = ResolvedConfig::from_file(files, config)?.with_context(|| {
+        format!(
+            "Config file was not found at the specified path: '{}'",
+            config.display()
+        )
+    })?;
+
+    Ok(Some(config))
 }
 
 fn read_input(input: &str) -> Result<String> {
@@ -1,40 +1,172 @@
-use super::{Config, Group, Indent, Preset, Punct, Quote, Space};
-use crate::yaml::{self, Deserialize, Node, NodeExt, Result};
-use decondenser::BreakStyle;
+use super::{Config, Extends, Glob, List, Override, Preset, Settings};
+use crate::Diagnostic;
+use crate::yaml::{self, Deserialize, Node, NodeExt, Object, Result};
+use decondenser::{
+    BreakStyle, GroupConfig, IndentStyle, LayoutAlgorithm, PunctConfig, QuoteConfig, SpaceConfig,
+    VisualSizeConfig,
+};
 
 impl Deserialize for Config {
     fn deserialize(value: Node) -> Result<Self> {
         value.object(|obj| Self {
-            extends: obj.optional("extends"),
-            indent: obj.optional("indent"),
+            extends: obj
+                .optional::<OneOrMany<Extends>>("extends")
+                .map(|extends| extends.0),
+            overrides: obj.optional("overrides"),
+            settings: Settings::from_object(obj),
+        })
+    }
+}
+
+impl Deserialize for Override {
+    fn deserialize(value: Node) -> Result<Self> {
+        value.object(|obj| Self {
+            files: obj
+                .optional::<OneOrMany<Glob>>("files")
+                .map(|files| files.0),
+            profile: obj.optional("profile"),
+            settings: Settings::from_object(obj),
+        })
+    }
+}
+
+impl Deserialize for Glob {
+    fn deserialize(value: Node) -> Result<Self> {
+        let span = match &value {
+            Node::Scalar(scalar) => yaml::scalar_span(scalar),
+            _ => *value.span(),
+        };
+
+        value
+            .any_of()
+            .string(|glob| {
+                let matcher = globset::GlobBuilder::new(&glob)
+                    .literal_separator(true)
+                    .build()
+                    .map_err(|err| {
+                        Diagnostic::error()
+                            .with_message(format!("invalid glob: {}", err.kind()))
+                            .with_labels(vec![
+                                yaml::primary_label(span).with_message("invalid glob"),
+                            ])
+                    })?
+                    .compile_matcher();
+
+                Ok(Self {
+                    matcher,
+                    file_name_only: !glob.contains('/'),
+                })
+            })
+            .finish()
+    }
+}
+
+impl Settings {
+    /// The settings are flattened into the objects of the config and overrides
+    fn from_object(obj: &mut Object) -> Self {
+        Self {
+            indent: obj.optional::<YamlIndent>("indent").map(|indent| indent.0),
+            tab_width: obj.optional("tab_width"),
             max_line_size: obj.optional("max_line_size"),
             no_break_size: obj.optional("no_break_size"),
+            visual_size: obj.optional("visual_size"),
+            layout_algorithm: obj
+                .optional::<YamlLayoutAlgorithm>("layout_algorithm")
+                .map(|algorithm| algorithm.0),
             groups: obj.optional("groups"),
             quotes: obj.optional("quotes"),
             puncts: obj.optional("puncts"),
+            wrap_raw_after: obj.optional("wrap_raw_after"),
+            escape_char: obj.optional("escape_char"),
             debug_layout: obj.optional("debug_layout"),
             debug_indent: obj.optional("debug_indent"),
-        })
+        }
+    }
+}
+
+/// A single item or a list of them
+struct OneOrMany<T>(Vec<T>);
+
+impl<T: Deserialize> Deserialize for OneOrMany<T> {
+    fn deserialize(value: Node) -> Result<Self> {
+        if let Node::Sequence(_) = value {
+            return Vec::deserialize(value).map(Self);
+        }
+
+        T::deserialize(value).map(|item| Self(vec![item]))
+    }
+}
+
+impl Deserialize for Extends {
+    /// The names of the presets take priority over the paths. A file named
+    /// the same as a preset can be referenced via `./{name}`.
+    fn deserialize(value: Node) -> Result<Self> {
+        let span = match &value {
+            Node::Scalar(scalar) => yaml::scalar_span(scalar),
+            _ => *value.span(),
+        };
+
+        value
+            .any_of()
+            .enumeration(&[
+                ("empty", || Self::Preset(Preset::Empty)),
+                ("generic", || Self::Preset(Preset::Generic)),
+            ])
+            .string(|path| Ok(Self::File { path, span }))
+            .finish()
+    }
+}
+
+impl<T: Deserialize> Deserialize for List<T> {
+    fn deserialize(value: Node) -> Result<Self> {
+        value
+            .any_of()
+            .array(|array| yaml::deserialize_array(array).map(Self::Replace))
+            .object(|obj| Self::Patch {
+                add: obj.optional("add").unwrap_or_default(),
+                remove: obj.optional("remove").unwrap_or_default(),
+            })
+            .finish()
+    }
+}
+
+impl Deserialize for VisualSizeConfig {
+    fn deserialize(value: Node) -> Result<Self> {
+        value.enumeration(&[
+            ("chars", || Self::Chars),
+            ("width", || Self::Width),
+            ("graphemes", || Self::Graphemes),
+        ])
     }
 }
 
-impl Deserialize for Preset {
+struct YamlLayoutAlgorithm(LayoutAlgorithm);
+
+impl Deserialize for YamlLayoutAlgorithm {
     fn deserialize(value: Node) -> Result<Self> {
-        value.enumeration(&[("empty", || Self::Empty), ("generic", || Self::Generic)])
+        value
+            .enumeration(&[
+                ("oppen", LayoutAlgorithm::oppen),
+                ("wadler", LayoutAlgorithm::wadler),
+            ])
+            .map(Self)
     }
 }
 
-impl Deserialize for Indent {
+/// Number of spaces or a string
+struct YamlIndent(String);
+
+impl Deserialize for YamlIndent {
     fn deserialize(value: Node) -> Result<Self> {
         value
             .any_of()
-            .usize(|n_spaces| Ok(Self::NSpaces(n_spaces)))
-            .string(|string| Ok(Self::Str(string)))
+            .usize(|n_spaces| Ok(Self(" ".repeat(n_spaces))))
+            .string(|string| Ok(Self(string)))
             .finish()
     }
 }
 
-impl Deserialize for Group {
+impl Deserialize for GroupConfig {
     fn deserialize(value: Node) -> Result<Self> {
         let span = *value.span();
 
@@ -50,9 +182,13 @@ impl Deserialize for Group {
                 })?;
 
                 Ok(Self {
-                    opening: Punct::deserialize(opening)?,
-                    closing: Punct::deserialize(closing)?,
+                    opening: PunctConfig::deserialize(opening)?,
+                    closing: PunctConfig::deserialize(closing)?,
                     break_style: None,
+                    indent_style: None,
+                    indent: None,
+                    align_after: None,
+                    max_align_padding: None,
                 })
             })
             .object(|obj| Self {
@@ -61,12 +197,18 @@ impl Deserialize for Group {
                 break_style: obj
                     .optional::<YamlBreakStyle>("break_style")
                     .map(|style| style.0),
+                indent_style: obj
+                    .optional::<YamlIndentStyle>("indent_style")
+                    .map(|style| style.0),
+                indent: obj.optional::<YamlIndent>("indent").map(|indent| indent.0),
+                align_after: obj.optional("align_after"),
+                max_align_padding: obj.optional("max_align_padding"),
             })
             .finish()
     }
 }
 
-impl Deserialize for Punct {
+impl Deserialize for PunctConfig {
     fn deserialize(value: Node) -> Result<Self> {
         value
             .any_of()
@@ -86,7 +228,7 @@ impl Deserialize for Punct {
     }
 }
 
-impl Deserialize for Space {
+impl Deserialize for SpaceConfig {
     fn deserialize(value: Node) -> Result<Self> {
         value
             .any_of()
@@ -109,19 +251,37 @@ struct YamlBreakStyle(BreakStyle);
 impl Deserialize for YamlBreakStyle {
     fn deserialize(value: Node) -> Result<Self> {
         value
+            .any_of()
             .enumeration(&[
                 ("consistent", BreakStyle::consistent),
                 ("compact", BreakStyle::compact),
+                ("table", BreakStyle::table),
+                ("fill", BreakStyle::fill),
             ])
+            .object(|obj| {
+                BreakStyle::consistent_if_more_than(obj.required("consistent_if_more_than"))
+            })
+            .finish()
+            .map(Self)
+    }
+}
+
+struct YamlIndentStyle(IndentStyle);
+
+impl Deserialize for YamlIndentStyle {
+    fn deserialize(value: Node) -> Result<Self> {
+        value
+            .enumeration(&[("block", IndentStyle::block), ("align", IndentStyle::align)])
             .map(Self)
     }
 }
 
-impl Deserialize for Quote {
+impl Deserialize for QuoteConfig {
     fn deserialize(value: Node) -> Result<Self> {
         value.object(|obj| Self {
             opening: obj.required("opening"),
             closing: obj.required("closing"),
+            concat_operator: obj.optional("concat_operator"),
         })
     }
 }
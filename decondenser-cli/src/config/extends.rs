@@ -0,0 +1,120 @@
+use super::{Config, Extends, Preset};
+use crate::yaml::primary_label;
+use crate::{Diagnostic, Files, Result};
+use marked_yaml::Span;
+use std::path::{Path, PathBuf};
+
+/// Config with the `extends` chain resolved into the list of layers. Every
+/// layer is applied on top of the previous ones, and the first layer is
+/// applied on top of the preset.
+#[derive(Default)]
+pub(crate) struct ResolvedConfig {
+    pub(super) preset: Preset,
+    pub(super) layers: Vec<Layer>,
+}
+
+pub(super) struct Layer {
+    /// Path of the config file
+    pub(super) path: PathBuf,
+
+    /// Directory of the config file, that the `overrides` are relative to
+    pub(super) dir: PathBuf,
+    pub(super) config: Config,
+}
+
+impl ResolvedConfig {
+    /// Paths of the config files in the order they are applied
+    pub(crate) fn files(&self) -> impl Iterator<Item = &Path> {
+        self.layers.iter().map(|layer| layer.path.as_path())
+    }
+
+    pub(super) fn resolve(files: &mut Files, path: &Path, config: Config) -> Result<Self> {
+        let mut resolver = Resolver {
+            files,
+            stack: vec![canonical(path)],
+            resolved: Self::default(),
+        };
+
+        resolver.push_config(path, config)?;
+
+        Ok(resolver.resolved)
+    }
+}
+
+struct Resolver<'a> {
+    files: &'a mut Files,
+
+    /// Config files that are being resolved. Used to detect cycles.
+    stack: Vec<PathBuf>,
+    resolved: ResolvedConfig,
+}
+
+impl Resolver<'_> {
+    fn push_config(&mut self, path: &Path, mut config: Config) -> Result {
+        let dir = path
+            .parent()
+            .filter(|dir| !dir.as_os_str().is_empty())
+            .unwrap_or_else(|| Path::new("."));
+
+        let dir = std::path::absolute(dir).unwrap_or_else(|_| dir.to_owned());
+
+        for extends in config.extends.take().into_iter().flatten() {
+            match extends {
+                // The preset overrides everything that was extended before it
+                Extends::Preset(preset) => {
+                    self.resolved.preset = preset;
+                    self.resolved.layers.clear();
+                }
+                Extends::File { path, span } => self.push_file(&dir.join(path), span)?,
+            }
+        }
+
+        let path = std::path::absolute(path).unwrap_or_else(|_| path.to_owned());
+
+        self.resolved.layers.push(Layer { path, dir, config });
+
+        Ok(())
+    }
+
+    fn push_file(&mut self, path: &Path, span: Span) -> Result {
+        let canonical = canonical(path);
+
+        if self.stack.contains(&canonical) {
+            let cycle = self
+                .stack
+                .iter()
+                .chain([&canonical])
+                .map(|path| path.display().to_string())
+                .collect::<Vec<_>>()
+                .join(" -> ");
+
+            let diag = Diagnostic::error()
+                .with_message("config files extend each other in a cycle")
+                .with_labels(vec![primary_label(span).with_message("cyclic extends")])
+                .with_note(cycle);
+
+            return Err(vec![diag].into());
+        }
+
+        let Some(config) = Config::from_file(self.files, path)? else {
+            let diag = Diagnostic::error()
+                .with_message(format!(
+                    "extended config file was not found: '{}'",
+                    path.display()
+                ))
+                .with_labels(vec![primary_label(span).with_message("extended here")]);
+
+            return Err(vec![diag].into());
+        };
+
+        self.stack.push(canonical);
+        self.push_config(path, config)?;
+        self.stack.pop();
+
+        Ok(())
+    }
+}
+
+fn canonical(path: &Path) -> PathBuf {
+    std::fs::canonicalize(path).unwrap_or_else(|_| path.to_owned())
+}
@@ -1,124 +1,169 @@
-use super::{Config, Group, Indent, Preset, Punct, Quote, Space};
-
-impl Config {
-    pub(crate) fn into_decondenser(self) -> decondenser::Decondenser {
-        let Self {
-            extends,
-            indent,
-            max_line_size,
-            no_break_size,
-            groups,
-            quotes,
-            puncts,
-            debug_layout,
-            debug_indent,
-        } = self;
+use super::extends::Layer;
+use super::{Glob, List, Override, Preset, ResolvedConfig, Settings};
+use std::path::{Path, PathBuf};
+
+impl ResolvedConfig {
+    /// The `input` is the path of the input file if it's not stdin, and the
+    /// `profile` is the name of the overrides selected explicitly.
+    pub(crate) fn into_decondenser(
+        self,
+        input: Option<&Path>,
+        profile: Option<&str>,
+    ) -> decondenser::Decondenser {
+        let input = input.map(absolute);
+
+        self.layers
+            .into_iter()
+            .fold(self.preset.into_decondenser(), |decondenser, layer| {
+                layer.apply(decondenser, input.as_deref(), profile)
+            })
+    }
 
-        let mut decondenser = match extends.unwrap_or(Preset::Generic) {
-            Preset::Empty => decondenser::Decondenser::empty(),
-            Preset::Generic => decondenser::Decondenser::generic(),
-        };
+    /// Names of the profiles defined in the overrides
+    pub(crate) fn profiles(&self) -> impl Iterator<Item = &str> {
+        self.layers
+            .iter()
+            .flat_map(|layer| layer.config.overrides.iter().flatten())
+            .filter_map(|overrides| overrides.profile.as_deref())
+    }
+}
 
-        if let Some(indent) = indent {
-            decondenser = match indent {
-                Indent::NSpaces(n_spaces) => decondenser.indent(n_spaces),
-                Indent::Str(string) => decondenser.indent(string),
-            }
-        }
+fn absolute(path: &Path) -> PathBuf {
+    std::path::absolute(path).unwrap_or_else(|_| path.to_owned())
+}
 
-        if let Some(max_line_size) = max_line_size {
-            decondenser = decondenser.max_line_size(max_line_size);
-        }
+impl Layer {
+    fn apply(
+        self,
+        decondenser: decondenser::Decondenser,
+        input: Option<&Path>,
+        profile: Option<&str>,
+    ) -> decondenser::Decondenser {
+        let Self {
+            path: _,
+            dir,
+            config,
+        } = self;
 
-        if let Some(no_break_size) = no_break_size {
-            decondenser = decondenser.no_break_size(no_break_size);
-        }
+        let decondenser = config.settings.apply(decondenser);
 
-        if let Some(groups) = groups {
-            decondenser = decondenser.groups(groups.into_iter().map(Group::into_core));
-        }
+        config
+            .overrides
+            .into_iter()
+            .flatten()
+            .filter(|overrides| overrides.matches(&dir, input, profile))
+            .fold(decondenser, |decondenser, overrides| {
+                overrides.settings.apply(decondenser)
+            })
+    }
+}
 
-        if let Some(quotes) = quotes {
-            decondenser = decondenser.quotes(quotes.into_iter().map(Quote::into_core));
+impl Override {
+    fn matches(&self, dir: &Path, input: Option<&Path>, profile: Option<&str>) -> bool {
+        if profile.is_some() && self.profile.as_deref() == profile {
+            return true;
         }
 
-        if let Some(puncts) = puncts {
-            decondenser = decondenser.puncts(puncts.into_iter().map(Punct::into_core));
-        }
+        let (Some(files), Some(input)) = (&self.files, input) else {
+            return false;
+        };
 
-        if let Some(debug_layout) = debug_layout {
-            decondenser = decondenser.debug_layout(debug_layout);
-        }
+        files.iter().any(|glob| glob.matches(dir, input))
+    }
+}
 
-        if let Some(debug_indent) = debug_indent {
-            decondenser = decondenser.debug_indent(debug_indent);
+impl Glob {
+    fn matches(&self, dir: &Path, input: &Path) -> bool {
+        if self.file_name_only {
+            return input
+                .file_name()
+                .is_some_and(|file_name| self.matcher.is_match(file_name));
         }
 
-        decondenser
+        input
+            .strip_prefix(dir)
+            .is_ok_and(|relative| self.matcher.is_match(relative))
     }
 }
 
-impl Group {
-    fn into_core(self) -> decondenser::Group {
-        let Self {
-            opening,
-            closing,
-            break_style,
-        } = self;
-
-        let mut group = decondenser::Group::new(opening.into_core(), closing.into_core());
-
-        if let Some(break_style) = break_style {
-            group = group.break_style(break_style);
+impl Preset {
+    fn into_decondenser(self) -> decondenser::Decondenser {
+        match self {
+            Self::Empty => decondenser::Decondenser::empty(),
+            Self::Generic => decondenser::Decondenser::generic(),
         }
-
-        group
     }
 }
 
-impl Punct {
-    fn into_core(self) -> decondenser::Punct {
+impl Settings {
+    /// Applies these settings on top of the given ones
+    fn apply(self, decondenser: decondenser::Decondenser) -> decondenser::Decondenser {
         let Self {
-            symbol,
-            leading_space,
-            trailing_space,
+            indent,
+            tab_width,
+            max_line_size,
+            no_break_size,
+            visual_size,
+            layout_algorithm,
+            groups,
+            quotes,
+            puncts,
+            wrap_raw_after,
+            escape_char,
+            debug_layout,
+            debug_indent,
         } = self;
 
-        let mut punct = decondenser::Punct::new(symbol);
+        let (groups, groups_patch) = groups.map(List::split).unwrap_or_default();
+        let (quotes, quotes_patch) = quotes.map(List::split).unwrap_or_default();
+        let (puncts, puncts_patch) = puncts.map(List::split).unwrap_or_default();
 
-        if let Some(leading_space) = leading_space {
-            punct = punct.leading_space(leading_space.into_core());
-        }
+        let mut decondenser = decondenser.apply_config(decondenser::DecondenserConfig {
+            indent,
+            tab_width,
+            max_line_size,
+            no_break_size,
+            visual_size,
+            layout_algorithm,
+            strict: None,
+            groups,
+            puncts,
+            quotes,
+            wrap_raw_after,
+            escape_char,
+            debug_layout,
+            debug_indent,
+        });
 
-        if let Some(trailing_space) = trailing_space {
-            punct = punct.trailing_space(trailing_space.into_core());
+        if let Some((add, remove)) = groups_patch {
+            decondenser = decondenser
+                .remove_groups(remove)
+                .add_groups(add.into_iter().map(decondenser::Group::from_config));
         }
 
-        punct
-    }
-}
-
-impl Space {
-    fn into_core(self) -> decondenser::Space {
-        let Self { size, breakable } = self;
-
-        let mut space = decondenser::Space::new();
-
-        if let Some(size) = size {
-            space = space.size(size);
+        if let Some((add, remove)) = quotes_patch {
+            decondenser = decondenser
+                .remove_quotes(remove)
+                .add_quotes(add.into_iter().map(decondenser::Quote::from_config));
         }
 
-        if let Some(breakable) = breakable {
-            space = space.breakable(breakable);
+        if let Some((add, remove)) = puncts_patch {
+            decondenser = decondenser
+                .remove_puncts(remove)
+                .add_puncts(add.into_iter().map(decondenser::Punct::from_config));
         }
 
-        space
+        decondenser
     }
 }
 
-impl Quote {
-    fn into_core(self) -> decondenser::Quote {
-        let Self { opening, closing } = self;
-        decondenser::Quote::new(opening, closing)
+impl<T> List<T> {
+    /// Splits the list into the replacement list for the
+    /// [`decondenser::DecondenserConfig`] and the items to add and remove
+    fn split(self) -> (Option<Vec<T>>, Option<(Vec<T>, Vec<String>)>) {
+        match self {
+            Self::Replace(items) => (Some(items), None),
+            Self::Patch { add, remove } => (None, Some((add, remove))),
+        }
     }
 }
@@ -1,20 +1,61 @@
 mod deser;
+mod extends;
 mod into_core;
+mod ser;
+
+pub(crate) use extends::ResolvedConfig;
+pub(crate) use ser::to_yaml;
 
 use crate::{Files, Result};
 use anyhow::Context;
-use decondenser::BreakStyle;
+use decondenser::{GroupConfig, LayoutAlgorithm, PunctConfig, QuoteConfig, VisualSizeConfig};
+use marked_yaml::Span;
 use std::path::Path;
 
+/// JSON Schema of the config file. Must be kept in sync with the
+/// [`Deserialize`](crate::yaml::Deserialize) implementations in `deser.rs`.
+pub(crate) const SCHEMA: &str = include_str!("../../config.schema.json");
+
+/// Commented config file written by `decondenser config init`
+const STARTER: &str = include_str!("starter.yml");
+
+/// Names of the config files in the order of priority if there are several of
+/// them in the same directory. `pyproject.toml` and `Cargo.toml` are only used
+/// if they have the embedded decondenser config table.
+const CONFIG_FILES: &[&str] = &[
+    "decondenser.yml",
+    "decondenser.toml",
+    ".decondenser.json",
+    "pyproject.toml",
+    "Cargo.toml",
+];
+
 #[derive(Default)]
 pub(crate) struct Config {
-    extends: Option<Preset>,
-    indent: Option<Indent>,
+    /// Presets or other config files this config is applied on top of
+    extends: Option<Vec<Extends>>,
+
+    /// Settings applied on top of the [`Config::settings`] only for some of
+    /// the inputs
+    overrides: Option<Vec<Override>>,
+    settings: Settings,
+}
+
+#[derive(Default)]
+struct Settings {
+    indent: Option<String>,
+    tab_width: Option<usize>,
     max_line_size: Option<usize>,
     no_break_size: Option<usize>,
-    groups: Option<Vec<Group>>,
-    quotes: Option<Vec<Quote>>,
-    puncts: Option<Vec<Punct>>,
+    visual_size: Option<VisualSizeConfig>,
+    layout_algorithm: Option<LayoutAlgorithm>,
+    groups: Option<List<GroupConfig>>,
+    quotes: Option<List<QuoteConfig>>,
+    puncts: Option<List<PunctConfig>>,
+    wrap_raw_after: Option<Vec<String>>,
+
+    // No stability guarantees are provided for this one yet
+    escape_char: Option<char>,
 
     // Only used for debugging. No stability guarantees are provided for these
     //
@@ -24,49 +65,89 @@ pub(crate) struct Config {
     debug_indent: Option<bool>,
 }
 
-enum Preset {
-    Empty,
-    Generic,
+struct Override {
+    /// Globs matched against the path of the input file
+    files: Option<Vec<Glob>>,
+
+    /// Name used to select the override via the `--profile` CLI flag, which
+    /// is useful for the stdin input that has no path
+    profile: Option<String>,
+    settings: Settings,
 }
 
-enum Indent {
-    NSpaces(usize),
-    Str(String),
+struct Glob {
+    matcher: globset::GlobMatcher,
+
+    /// Same as in `.gitignore`, the glob without a `/` matches the file name
+    /// at any depth, and the glob with a `/` matches the path relative to the
+    /// directory of the config file
+    file_name_only: bool,
 }
 
-struct Group {
-    opening: Punct,
-    closing: Punct,
-    break_style: Option<BreakStyle>,
+enum Extends {
+    Preset(Preset),
+
+    /// Path relative to the directory of the config file
+    File {
+        path: String,
+        span: Span,
+    },
 }
 
 #[derive(Default)]
-struct Punct {
-    symbol: String,
-    leading_space: Option<Space>,
-    trailing_space: Option<Space>,
+enum Preset {
+    Empty,
+    #[default]
+    Generic,
 }
 
-struct Space {
-    size: Option<usize>,
-    breakable: Option<bool>,
-}
+/// List of items that either replaces the inherited list or modifies it
+enum List<T> {
+    Replace(Vec<T>),
 
-struct Quote {
-    opening: String,
-    closing: String,
+    /// The items are removed by their opening sequence or symbol first, and
+    /// then the new items are added
+    Patch {
+        add: Vec<T>,
+        remove: Vec<String>,
+    },
 }
 
-impl Config {
-    pub(crate) fn discover(files: &mut Files) -> Result<Option<Self>> {
-        std::env::current_dir()
-            .context("Failed to get the current directory of the process")?
+impl ResolvedConfig {
+    /// Looks for the config file in the given directory and its ancestors, or
+    /// the current directory if `None`.
+    pub(crate) fn discover(files: &mut Files, dir: Option<&Path>) -> Result<Option<Self>> {
+        let dir = match dir {
+            Some(dir) => std::path::absolute(dir),
+            None => std::env::current_dir(),
+        };
+
+        dir.context("Failed to get the directory to discover the config file in")?
             .ancestors()
-            .find_map(|path| Self::from_file(files, &path.join("decondenser.yml")).transpose())
+            .flat_map(|dir| CONFIG_FILES.iter().map(|file| dir.join(file)))
+            .find_map(|path| Self::from_file(files, &path).transpose())
             .transpose()
     }
 
     pub(crate) fn from_file(files: &mut Files, path: &Path) -> Result<Option<Self>> {
+        let Some(config) = Config::from_file(files, path)? else {
+            return Ok(None);
+        };
+
+        Self::resolve(files, path, config).map(Some)
+    }
+}
+
+/// Commented config file that extends the given preset
+pub(crate) fn starter(preset: &str) -> String {
+    STARTER.replace("%preset%", preset)
+}
+
+impl Config {
+    /// The format of the file is detected by its name. JSON is parsed as YAML,
+    /// because YAML is a superset of JSON. Returns `None` if the file doesn't
+    /// exist or doesn't have the embedded config table.
+    fn from_file(files: &mut Files, path: &Path) -> Result<Option<Self>> {
         let content = match std::fs::read_to_string(path) {
             Ok(content) => content,
             Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
@@ -80,8 +161,18 @@ impl Config {
         let file_id = files.add(path.to_string_lossy().into_owned(), content);
         let file = files.get(file_id).unwrap();
 
-        let config = crate::yaml::from_str(file_id, file.source())?;
+        let file_name = path.file_name().and_then(|name| name.to_str());
+        let is_toml = path
+            .extension()
+            .is_some_and(|extension| extension == "toml");
+
+        let table: &[&str] = match file_name {
+            Some("pyproject.toml") => &["tool", "decondenser"],
+            Some("Cargo.toml") => &["package", "metadata", "decondenser"],
+            _ if is_toml => &[],
+            _ => return Ok(Some(crate::yaml::from_str(file_id, file.source())?)),
+        };
 
-        Ok(Some(config))
+        Ok(crate::toml::from_str(file_id, file.source(), table)?)
     }
 }
@@ -0,0 +1,137 @@
+//! Serialization of the resolved config into YAML for `decondenser config show`.
+//! The output is a self-contained config that extends the empty preset, so it
+//! produces the same formatting if it's used as the config file as is.
+//!
+//! The config is serialized via `serde` into a JSON value first, since the
+//! `serde` representation of [`DecondenserConfig`] matches the config file.
+
+use decondenser::DecondenserConfig;
+use serde_json::{Map, Value};
+use std::fmt::Write;
+
+/// Items of the lists that fit into this many columns are written in the
+/// flow style on a single line
+const MAX_FLOW_SIZE: usize = 80;
+
+pub(crate) fn to_yaml(mut config: DecondenserConfig) -> String {
+    // The strict mode isn't configurable in the config file
+    config.strict = None;
+
+    // The default depends on the max line size, so it's shown explicitly
+    config.no_break_size = config
+        .no_break_size
+        .or_else(|| Some(config.max_line_size? / 2));
+
+    let Value::Object(mut config) = serde_json::to_value(config).unwrap() else {
+        unreachable!("DecondenserConfig is serialized as an object");
+    };
+
+    shorten_indent(&mut config);
+
+    for group in config
+        .get_mut("groups")
+        .and_then(Value::as_array_mut)
+        .into_iter()
+        .flatten()
+    {
+        if let Value::Object(group) = group {
+            shorten_indent(group);
+        }
+    }
+
+    let mut yaml = String::from("extends: empty");
+
+    for (key, value) in config {
+        match value {
+            Value::Array(items) if !items.is_empty() && !items.iter().all(is_scalar) => {
+                write!(yaml, "\n{key}:").unwrap();
+
+                for item in items {
+                    write!(yaml, "\n  - ").unwrap();
+                    write_list_item(&mut yaml, &item);
+                }
+            }
+            value => write!(yaml, "\n{key}: {}", flow(&value)).unwrap(),
+        }
+    }
+
+    yaml
+}
+
+/// The indent that consists only of spaces is written as the number of them
+fn shorten_indent(object: &mut Map<String, Value>) {
+    let Some(indent) = object.get_mut("indent") else {
+        return;
+    };
+
+    let n_spaces = indent
+        .as_str()
+        .filter(|indent| indent.chars().all(|char| char == ' '))
+        .map(str::len);
+
+    if let Some(n_spaces) = n_spaces {
+        *indent = n_spaces.into();
+    }
+}
+
+/// The item is written in the flow style if it's short enough, or as a block
+/// mapping with the values in the flow style otherwise
+fn write_list_item(yaml: &mut String, item: &Value) {
+    let flow_item = flow(item);
+
+    let Value::Object(object) = item else {
+        yaml.push_str(&flow_item);
+        return;
+    };
+
+    if flow_item.len() <= MAX_FLOW_SIZE {
+        yaml.push_str(&flow_item);
+        return;
+    }
+
+    let entries = object
+        .iter()
+        .map(|(key, value)| format!("{key}: {}", flow(value)))
+        .collect::<Vec<_>>();
+
+    yaml.push_str(&entries.join("\n    "));
+}
+
+fn flow(value: &Value) -> String {
+    match value {
+        Value::Array(items) => {
+            let items = items.iter().map(flow).collect::<Vec<_>>();
+            format!("[{}]", items.join(", "))
+        }
+        Value::Object(object) if object.is_empty() => "{}".to_owned(),
+        Value::Object(object) => {
+            let entries = object
+                .iter()
+                .map(|(key, value)| format!("{key}: {}", flow(value)))
+                .collect::<Vec<_>>();
+
+            format!("{{ {} }}", entries.join(", "))
+        }
+        // Plain words such as the names of enum variants read better unquoted
+        Value::String(string) if is_plain(string) => string.clone(),
+
+        // JSON scalars are valid YAML scalars
+        scalar => scalar.to_string(),
+    }
+}
+
+/// Whether the string can be written without quotes and isn't parsed as a
+/// scalar of another type (e.g. `true` or `null`)
+fn is_plain(string: &str) -> bool {
+    !matches!(
+        string,
+        "true" | "false" | "null" | "yes" | "no" | "on" | "off"
+    ) && string.starts_with(|char: char| char.is_ascii_lowercase())
+        && string
+            .chars()
+            .all(|char| char.is_ascii_lowercase() || char == '_')
+}
+
+fn is_scalar(value: &Value) -> bool {
+    !matches!(value, Value::Array(_) | Value::Object(_))
+}
@@ -5,6 +5,7 @@ mod cli;
 mod config;
 mod error;
 mod styles;
+mod toml;
 mod yaml;
 
 use codespan_reporting::files::SimpleFiles;
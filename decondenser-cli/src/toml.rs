@@ -0,0 +1,125 @@
+//! TOML configs are converted into the YAML nodes with the spans pointing to
+//! the TOML source. This way the same [`Deserialize`] implementations and
+//! diagnostics are reused for both formats.
+
+use crate::yaml::{Deserialize, Result};
+use crate::{Diagnostic, Label};
+use hashlink::LinkedHashMap;
+use marked_yaml::types::{MarkedMappingNode, MarkedScalarNode, MarkedSequenceNode};
+use marked_yaml::{Marker, Node, Span};
+use std::ops::Range;
+use toml_edit::{Item, TableLike, Value};
+
+/// Deserializes the table at the given path of keys in the TOML document, or
+/// the entire document if the path is empty. Returns `None` if there is no
+/// such table in the document.
+pub(crate) fn from_str<T: Deserialize>(
+    file_id: usize,
+    input: &str,
+    table: &[&str],
+) -> Result<Option<T>> {
+    let document = toml_edit::Document::parse(input).map_err(|err| {
+        let span = err.span().unwrap_or(0..0);
+
+        Diagnostic::error()
+            .with_message("failed to parse TOML")
+            .with_label(Label::primary(file_id, span).with_message(err.message()))
+    })?;
+
+    let mut item = document.as_item();
+    let mut span = None;
+
+    for key in table {
+        let Some((key, value)) = item
+            .as_table_like()
+            .and_then(|table| table.get_key_value(key))
+        else {
+            return Ok(None);
+        };
+
+        span = key.span();
+        item = value;
+    }
+
+    let converter = Converter { file_id, input };
+
+    T::deserialize(converter.item(item, span)).map(Some)
+}
+
+struct Converter<'a> {
+    file_id: usize,
+    input: &'a str,
+}
+
+impl Converter<'_> {
+    /// Tables have no spans in TOML, so the span of the key that refers to the
+    /// item is used as a fallback.
+    fn item(&self, item: &Item, key_span: Option<Range<usize>>) -> Node {
+        let span = item.span().or(key_span);
+
+        match item {
+            Item::None => MarkedScalarNode::new(self.span(span), "").into(),
+            Item::Value(value) => self.value(value),
+            Item::Table(table) => self.table(table, span),
+            Item::ArrayOfTables(array) => {
+                let items = array
+                    .iter()
+                    .map(|table| self.table(table, span.clone()))
+                    .collect();
+
+                MarkedSequenceNode::new(self.span(span), items).into()
+            }
+        }
+    }
+
+    fn value(&self, value: &Value) -> Node {
+        let span = self.span(value.span());
+
+        let scalar = match value {
+            Value::String(string) => string.value().clone(),
+            Value::Integer(integer) => integer.value().to_string(),
+            Value::Float(float) => float.value().to_string(),
+            Value::Boolean(boolean) => boolean.value().to_string(),
+            Value::Datetime(datetime) => datetime.value().to_string(),
+            Value::Array(array) => {
+                let items = array.iter().map(|value| self.value(value)).collect();
+                return MarkedSequenceNode::new(span, items).into();
+            }
+            Value::InlineTable(table) => return self.table(table, value.span()),
+        };
+
+        MarkedScalarNode::new(span, scalar).into()
+    }
+
+    fn table(&self, table: &dyn TableLike, span: Option<Range<usize>>) -> Node {
+        let entries = table
+            .iter()
+            .filter_map(|(key, _)| table.get_key_value(key))
+            .map(|(key, item)| {
+                let key_node = MarkedScalarNode::new(self.span(key.span()), key.get());
+                (key_node, self.item(item, key.span()))
+            })
+            .collect::<LinkedHashMap<_, _>>();
+
+        MarkedMappingNode::new(self.span(span), entries).into()
+    }
+
+    fn span(&self, range: Option<Range<usize>>) -> Span {
+        let Some(range) = range else {
+            return Span::new_blank();
+        };
+
+        Span::new_with_marks(self.marker(range.start), self.marker(range.end))
+    }
+
+    /// The markers use byte offsets, because they are passed to the labels of
+    /// the diagnostics as is
+    fn marker(&self, offset: usize) -> Marker {
+        let before = &self.input[..offset];
+        let line_start = before.rfind('\n').map(|index| index + 1).unwrap_or(0);
+        let line = before.matches('\n').count() + 1;
+        let column = before[line_start..].chars().count() + 1;
+
+        Marker::new(self.file_id, offset, line, column)
+    }
+}
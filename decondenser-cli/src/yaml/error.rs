@@ -162,7 +162,7 @@ fn label_with_style(style: LabelStyle, span: Span) -> Label {
     Label::new(style, source, start..end)
 }
 
-fn scalar_span(scalar: &MarkedScalarNode) -> Span {
+pub(crate) fn scalar_span(scalar: &MarkedScalarNode) -> Span {
     let mut span: Span = *scalar.span();
 
     // There seems to be a bug in `marked_yaml` where the end span
@@ -1,8 +1,8 @@
 mod any_of;
 mod error;
 
-pub(crate) use any_of::NodeExt;
-pub(crate) use error::{Errors, Result};
+pub(crate) use any_of::{NodeExt, Object};
+pub(crate) use error::{Errors, Result, primary_label, scalar_span};
 pub(crate) use marked_yaml::Node;
 
 use std::collections::BTreeMap;
@@ -48,23 +48,22 @@ impl Deserialize for char {
 
 impl<T: Deserialize> Deserialize for Vec<T> {
     fn deserialize(value: Node) -> Result<Self> {
-        value
-            .any_of()
-            .array(|array| {
-                let mut errors = Errors::default();
-                let mut output = Self::with_capacity(array.len());
+        value.any_of().array(deserialize_array).finish()
+    }
+}
 
-                for value in array {
-                    match T::deserialize(value) {
-                        Ok(value) => output.push(value),
-                        Err(err) => errors.extend([err]),
-                    }
-                }
+pub(crate) fn deserialize_array<T: Deserialize>(array: Vec<Node>) -> Result<Vec<T>> {
+    let mut errors = Errors::default();
+    let mut output = Vec::with_capacity(array.len());
 
-                errors.into_result().map(|()| output)
-            })
-            .finish()
+    for value in array {
+        match T::deserialize(value) {
+            Ok(value) => output.push(value),
+            Err(err) => errors.extend([err]),
+        }
     }
+
+    errors.into_result().map(|()| output)
 }
 
 impl<T: Deserialize> Deserialize for BTreeMap<String, T> {
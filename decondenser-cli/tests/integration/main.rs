@@ -1,5 +1,10 @@
 #![expect(missing_docs)]
 
+use marked_yaml::Node;
+use std::collections::BTreeSet;
+use std::ffi::OsStr;
+use std::path::Path;
+
 // Verify that the CLI is runnable at all. It also validates some clap
 // invariants, that are checked only at runtime (e.g. if arg names are reused).
 #[test]
@@ -25,3 +30,362 @@ fn smoke() {
         concat!("decondenser ", env!("CARGO_PKG_VERSION"))
     );
 }
+
+#[test]
+fn config_extends() {
+    let dir = test_dir("config_extends");
+    std::fs::create_dir_all(dir.join("nested")).unwrap();
+
+    std::fs::write(dir.join("decondenser.yml"), "max_line_size: 20\n").unwrap();
+    std::fs::write(
+        dir.join("nested/decondenser.yml"),
+        "extends: ../decondenser.yml\ngroups:\n  remove: ['(']\n",
+    )
+    .unwrap();
+
+    let config = dir.join("nested/decondenser.yml");
+    let output = fmt(
+        &["--config".as_ref(), config.as_os_str()],
+        "(aaaa, bbbb) [cccc, dddd, eeee]",
+    );
+
+    assert_eq!(output, "(aaaa,\nbbbb) [\n    cccc,\n    dddd,\n    eeee\n]");
+}
+
+#[test]
+fn config_overrides() {
+    let dir = test_dir("config_overrides");
+    std::fs::create_dir_all(&dir).unwrap();
+
+    std::fs::write(
+        dir.join("decondenser.yml"),
+        "max_line_size: 20\n\
+        overrides:\n\
+        \x20 - files: '*.ex.log'\n\
+        \x20   profile: elixir\n\
+        \x20   indent: 2\n",
+    )
+    .unwrap();
+
+    let input = "[aaaa, bbbb, cccc, dddd]";
+    let file = dir.join("dump.ex.log");
+    std::fs::write(&file, input).unwrap();
+
+    let expected = "[\n  aaaa,\n  bbbb,\n  cccc,\n  dddd\n]";
+
+    assert_eq!(fmt(&["--input".as_ref(), file.as_os_str()], ""), expected);
+
+    let config = dir.join("decondenser.yml");
+    let args = [
+        "--config".as_ref(),
+        config.as_os_str(),
+        "--profile".as_ref(),
+        "elixir".as_ref(),
+    ];
+
+    assert_eq!(fmt(&args, input), expected);
+}
+
+#[test]
+fn config_formats() {
+    let configs = [
+        ("decondenser.toml", "max_line_size = 20\nindent = 2\n"),
+        (
+            ".decondenser.json",
+            "{ \"max_line_size\": 20, \"indent\": 2 }\n",
+        ),
+        (
+            "pyproject.toml",
+            "[project]\nname = 'test'\n\n[tool.decondenser]\nmax_line_size = 20\nindent = 2\n",
+        ),
+        (
+            "Cargo.toml",
+            "[package]\nname = 'test'\n\n\
+            [package.metadata.decondenser]\nmax_line_size = 20\nindent = 2\n",
+        ),
+    ];
+
+    for (file_name, config) in configs {
+        let dir = test_dir("config_formats").join(file_name);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join(file_name), config).unwrap();
+
+        // The config is discovered in the directory of the input file
+        let input = dir.join("input.txt");
+        std::fs::write(&input, "[aaaa, bbbb, cccc, dddd]").unwrap();
+
+        assert_eq!(
+            fmt(&["--input".as_ref(), input.as_os_str()], ""),
+            "[\n  aaaa,\n  bbbb,\n  cccc,\n  dddd\n]",
+            "{file_name}"
+        );
+    }
+}
+
+#[test]
+fn config_init_validate_show() {
+    let dir = test_dir("config_init_validate_show");
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let config = dir.join("decondenser.yml");
+    std::fs::remove_file(&config).ok();
+
+    let init = [
+        "config".as_ref(),
+        "init".as_ref(),
+        "--output".as_ref(),
+        config.as_os_str(),
+    ];
+
+    assert!(run(&init, "").status.success());
+    assert!(!run(&init, "").status.success(), "must not overwrite");
+
+    let validate = ["config".as_ref(), "validate".as_ref(), config.as_os_str()];
+    let output = run(&validate, "");
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    assert!(output.status.success());
+    assert!(stdout.contains(&*config.to_string_lossy()), "{stdout}");
+
+    std::fs::write(
+        &config,
+        "extends: generic\n\
+        max_line_size: 100\n\
+        overrides:\n\
+        \x20 - files: '*.log'\n\
+        \x20   visual_size: width\n\
+        \x20   groups: { remove: ['<<'] }\n",
+    )
+    .unwrap();
+
+    let input = dir.join("app.log");
+
+    let show = |config: &Path| {
+        let args = [
+            "config".as_ref(),
+            "show".as_ref(),
+            "--config".as_ref(),
+            config.as_os_str(),
+            "--input".as_ref(),
+            input.as_os_str(),
+            "--indent".as_ref(),
+            "2".as_ref(),
+        ];
+
+        let output = run(&args, "");
+        assert!(output.status.success());
+
+        // Skip the comments with the paths of the config files
+        String::from_utf8(output.stdout)
+            .unwrap()
+            .lines()
+            .filter(|line| !line.starts_with('#'))
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    let shown = show(&config);
+
+    for line in ["indent: 2", "max_line_size: 100", "visual_size: width"] {
+        assert!(shown.lines().any(|shown| shown == line), "{line}\n{shown}");
+    }
+
+    assert!(shown.contains("\"(\""), "{shown}");
+    assert!(!shown.contains("\"<<\""), "{shown}");
+
+    // The shown config is self-contained and resolves to itself
+    let shown_config = dir.join("shown.yml");
+    std::fs::write(&shown_config, &shown).unwrap();
+
+    assert_eq!(show(&shown_config), shown);
+}
+
+/// The schema must describe the same keys and enum variants as the ones that
+/// are accepted by the `Deserialize` impls of the config. They are compared
+/// by feeding the invalid configs to the CLI and inspecting the errors.
+#[test]
+fn config_schema() {
+    let output = run(&["config".as_ref(), "schema".as_ref()], "");
+    assert!(output.status.success());
+
+    let schema = String::from_utf8(output.stdout).unwrap();
+    let schema = marked_yaml::parse_yaml(0, &schema).unwrap();
+
+    let objects = [
+        ("", "__unknown__: 1"),
+        ("/definitions/override", "overrides: [{ __unknown__: 1 }]"),
+        (
+            "/definitions/groupList/anyOf/1",
+            "groups: { __unknown__: 1 }",
+        ),
+        (
+            "/definitions/punctList/anyOf/1",
+            "puncts: { __unknown__: 1 }",
+        ),
+        (
+            "/definitions/quoteList/anyOf/1",
+            "quotes: { __unknown__: 1 }",
+        ),
+        ("/definitions/group/anyOf/1", "groups: [{ __unknown__: 1 }]"),
+        ("/definitions/punct/anyOf/1", "puncts: [{ __unknown__: 1 }]"),
+        (
+            "/definitions/space/anyOf/1",
+            "puncts: [{ symbol: ',', leading_space: { __unknown__: 1 } }]",
+        ),
+        (
+            "/definitions/breakStyle/anyOf/1",
+            "groups: [{ opening: '(', closing: ')', break_style: { __unknown__: 1 } }]",
+        ),
+        ("/definitions/quote", "quotes: [{ __unknown__: 1 }]"),
+    ];
+
+    for (pointer, config) in objects {
+        let object = schema_node(&schema, pointer);
+        let stderr = config_errors(config);
+
+        let keys = |key| {
+            let Some(Node::Mapping(keys)) = object.as_mapping().unwrap().get_node(key) else {
+                return BTreeSet::new();
+            };
+            keys.keys().map(|key| key.as_str().to_owned()).collect()
+        };
+
+        let required: BTreeSet<_> = object
+            .as_mapping()
+            .unwrap()
+            .get_sequence("required")
+            .map(|required| {
+                required
+                    .iter()
+                    .map(|key| key.as_scalar().unwrap().as_str().to_owned())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let allowed = stderr
+            .lines()
+            .find_map(|line| line.split_once("allowed keys: "))
+            .map(|(_, keys)| keys.split(", ").map(str::to_owned).collect())
+            .unwrap_or_default();
+
+        let missing = stderr
+            .lines()
+            .filter_map(|line| line.split_once("missing key '"))
+            .map(|(_, key)| key.trim_end_matches('\'').to_owned())
+            .collect::<BTreeSet<_>>();
+
+        assert_eq!(keys("properties"), allowed, "{pointer}\n{stderr}");
+        assert_eq!(required, missing, "{pointer}\n{stderr}");
+    }
+
+    let enums = [
+        ("/definitions/preset", "extends: {}"),
+        ("/definitions/visualSize", "visual_size: []"),
+        ("/definitions/layoutAlgorithm", "layout_algorithm: []"),
+        (
+            "/definitions/breakStyle/anyOf/0",
+            "groups: [{ opening: '(', closing: ')', break_style: [] }]",
+        ),
+        (
+            "/definitions/indentStyle",
+            "groups: [{ opening: '(', closing: ')', indent_style: [] }]",
+        ),
+    ];
+
+    for (pointer, config) in enums {
+        let variants = schema_node(&schema, pointer)
+            .as_mapping()
+            .unwrap()
+            .get_sequence("enum")
+            .unwrap()
+            .iter()
+            .map(|variant| variant.as_scalar().unwrap().as_str().to_owned())
+            .collect::<BTreeSet<_>>();
+
+        let stderr = config_errors(config);
+
+        // The expected types such as "a string" or "an object" are skipped
+        let expected = stderr
+            .lines()
+            .find_map(|line| line.split_once("expected ")?.1.split_once(" but found"))
+            .unwrap_or_else(|| panic!("{pointer}\n{stderr}"))
+            .0
+            .split(" or ")
+            .filter(|variant| !variant.starts_with("a ") && !variant.starts_with("an "))
+            .map(str::to_owned)
+            .collect::<BTreeSet<_>>();
+
+        assert_eq!(variants, expected, "{pointer}\n{stderr}");
+    }
+}
+
+fn schema_node<'a>(schema: &'a Node, pointer: &str) -> &'a Node {
+    pointer
+        .split('/')
+        .skip(1)
+        .fold(schema, |node, key| match node {
+            Node::Mapping(mapping) => mapping.get_node(key).unwrap(),
+            Node::Sequence(sequence) => &sequence[key.parse::<usize>().unwrap()],
+            Node::Scalar(_) => panic!("Can't index into a scalar at {pointer}"),
+        })
+}
+
+/// Returns the errors reported by the CLI for the given invalid config
+fn config_errors(config: &str) -> String {
+    let dir = test_dir("config_schema");
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let path = dir.join("decondenser.yml");
+    std::fs::write(&path, config).unwrap();
+
+    let output = run(&["fmt".as_ref(), "--config".as_ref(), path.as_os_str()], "");
+    assert!(!output.status.success(), "{config}");
+
+    String::from_utf8(output.stderr).unwrap()
+}
+
+fn test_dir(name: &str) -> std::path::PathBuf {
+    Path::new(env!("CARGO_TARGET_TMPDIR")).join(name)
+}
+
+/// Runs `decondenser fmt` with the given args and stdin, and returns its stdout
+fn fmt(args: &[&OsStr], stdin: &str) -> String {
+    let args = [
+        &["fmt".as_ref()],
+        args,
+        &["--no-break-size".as_ref(), "0".as_ref()],
+    ]
+    .concat();
+    let output = run(&args, stdin);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(output.status.success(), "{stderr}");
+
+    String::from_utf8(output.stdout)
+        .unwrap()
+        .trim_end()
+        .to_owned()
+}
+
+fn run(args: &[&OsStr], stdin: &str) -> std::process::Output {
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_decondenser"))
+        .args(args)
+        .env("NO_COLOR", "1")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(stdin.as_bytes())
+        .unwrap();
+
+    child.wait_with_output().unwrap()
+}
@@ -24,5 +24,6 @@ step npx jco transpile \
     "${options[@]}" \
     --tla-compat \
     --base64-cutoff 200000 \
+    --map host=../host.mjs \
     --out-dir ./dist \
     ../target/wasm32-unknown-unknown/release/decondenser_wasm.wasm
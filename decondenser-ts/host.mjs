@@ -0,0 +1,18 @@
+// Implementation of the `host` interface imported by the WASM component.
+// See `decondenser-wasm/wit/world.wit`.
+
+/**
+ * Monotonic time in milliseconds
+ * @returns {number}
+ */
+export function now() {
+    return performance.now();
+}
+
+/**
+ * Receives the debug log messages if the `log` param is enabled
+ * @param {string} message
+ */
+export function log(message) {
+    console.debug(`[decondenser] ${message}`);
+}
@@ -1,68 +1,108 @@
-use crate::wit::{BreakStyle, DecondenserParams, Group, Indent, Preset, Punct, Quote, Space};
+use crate::wit::{
+    BreakStyle, DecondenserParams, Group, Indent, IndentStyle, LayoutAlgorithm, Preset, Punct,
+    Quote, Space, VisualSize,
+};
+use decondenser::{
+    DecondenserConfig, GroupConfig, PunctConfig, QuoteConfig, SpaceConfig, VisualSizeConfig,
+};
 
 impl DecondenserParams {
     pub(crate) fn into_decondenser(self) -> decondenser::Decondenser {
         let Self {
             extends,
             indent,
+            tab_width,
             max_line_size,
             no_break_size,
+            visual_size,
+            layout_algorithm,
             groups,
             quotes,
             puncts,
+            wrap_raw_after,
+            escape_char,
+            debug_layout,
+            debug_indent,
+
+            // Not a part of the core config. It's handled by the component
+            log: _,
         } = self;
 
-        let mut decondenser = match extends.unwrap_or(Preset::Generic) {
+        let decondenser = match extends.unwrap_or(Preset::Generic) {
             Preset::Empty => decondenser::Decondenser::empty(),
             Preset::Generic => decondenser::Decondenser::generic(),
         };
 
-        if let Some(indent) = indent {
-            decondenser = match indent {
-                Indent::NSpaces(n_spaces) => decondenser.indent(uint_to_core(n_spaces)),
-                Indent::Str(string) => decondenser.indent(string),
-            }
-        }
-
-        if let Some(max_line_size) = max_line_size {
-            decondenser = decondenser.max_line_size(uint_to_core(max_line_size));
-        }
+        let config = DecondenserConfig {
+            indent: indent.map(Indent::into_core),
+            tab_width: tab_width.map(uint_to_core),
+            max_line_size: max_line_size.map(uint_to_core),
+            no_break_size: no_break_size.map(uint_to_core),
+            visual_size: visual_size.map(VisualSize::into_core),
+            layout_algorithm: layout_algorithm.map(LayoutAlgorithm::into_core),
+            strict: None,
+            groups: groups.map(|groups| groups.into_iter().map(Group::into_core).collect()),
+            quotes: quotes.map(|quotes| quotes.into_iter().map(Quote::into_core).collect()),
+            puncts: puncts.map(|puncts| puncts.into_iter().map(Punct::into_core).collect()),
+            wrap_raw_after,
+            escape_char,
+            debug_layout,
+            debug_indent,
+        };
 
-        if let Some(no_break_size) = no_break_size {
-            decondenser = decondenser.no_break_size(uint_to_core(no_break_size));
-        }
+        decondenser.apply_config(config)
+    }
+}
 
-        if let Some(groups) = groups {
-            decondenser = decondenser.groups(groups.into_iter().map(Group::into_core));
+impl Indent {
+    fn into_core(self) -> String {
+        match self {
+            Self::NSpaces(n_spaces) => " ".repeat(uint_to_core(n_spaces)),
+            Self::Str(string) => string,
         }
+    }
+}
 
-        if let Some(quotes) = quotes {
-            decondenser = decondenser.quotes(quotes.into_iter().map(Quote::into_core));
+impl VisualSize {
+    fn into_core(self) -> VisualSizeConfig {
+        match self {
+            Self::Chars => VisualSizeConfig::Chars,
+            Self::Width => VisualSizeConfig::Width,
+            Self::Graphemes => VisualSizeConfig::Graphemes,
         }
+    }
+}
 
-        if let Some(puncts) = puncts {
-            decondenser = decondenser.puncts(puncts.into_iter().map(Punct::into_core));
+impl LayoutAlgorithm {
+    fn into_core(self) -> decondenser::LayoutAlgorithm {
+        match self {
+            Self::Oppen => decondenser::LayoutAlgorithm::oppen(),
+            Self::Wadler => decondenser::LayoutAlgorithm::wadler(),
         }
-
-        decondenser
     }
 }
 
 impl Group {
-    fn into_core(self) -> decondenser::Group {
+    fn into_core(self) -> GroupConfig {
         let Self {
             opening,
             closing,
             break_style,
+            indent_style,
+            indent,
+            align_after,
+            max_align_padding,
         } = self;
 
-        let mut group = decondenser::Group::new(opening.into_core(), closing.into_core());
-
-        if let Some(break_style) = break_style {
-            group = group.break_style(break_style.into_core());
+        GroupConfig {
+            opening: opening.into_core(),
+            closing: closing.into_core(),
+            break_style: break_style.map(BreakStyle::into_core),
+            indent_style: indent_style.map(IndentStyle::into_core),
+            indent: indent.map(Indent::into_core),
+            align_after: align_after.map(Punct::into_core),
+            max_align_padding: max_align_padding.map(uint_to_core),
         }
-
-        group
     }
 }
 
@@ -71,54 +111,64 @@ impl BreakStyle {
         match self {
             Self::Consistent => decondenser::BreakStyle::consistent(),
             Self::Compact => decondenser::BreakStyle::compact(),
+            Self::Table => decondenser::BreakStyle::table(),
+            Self::Fill => decondenser::BreakStyle::fill(),
+            Self::ConsistentIfMoreThan(items) => {
+                decondenser::BreakStyle::consistent_if_more_than(uint_to_core(items))
+            }
+        }
+    }
+}
+
+impl IndentStyle {
+    fn into_core(self) -> decondenser::IndentStyle {
+        match self {
+            Self::Block => decondenser::IndentStyle::block(),
+            Self::Align => decondenser::IndentStyle::align(),
         }
     }
 }
 
 impl Punct {
-    fn into_core(self) -> decondenser::Punct {
+    fn into_core(self) -> PunctConfig {
         let Self {
             symbol,
             leading_space,
             trailing_space,
         } = self;
 
-        let mut punct = decondenser::Punct::new(symbol);
-
-        if let Some(leading_space) = leading_space {
-            punct = punct.leading_space(leading_space.into_core());
-        }
-
-        if let Some(trailing_space) = trailing_space {
-            punct = punct.trailing_space(trailing_space.into_core());
+        PunctConfig {
+            symbol,
+            leading_space: leading_space.map(Space::into_core),
+            trailing_space: trailing_space.map(Space::into_core),
         }
-
-        punct
     }
 }
 
 impl Space {
-    fn into_core(self) -> decondenser::Space {
+    fn into_core(self) -> SpaceConfig {
         let Self { size, breakable } = self;
 
-        let mut space = decondenser::Space::new();
-
-        if let Some(size) = size {
-            space = space.size(uint_to_core(size));
+        SpaceConfig {
+            size: size.map(uint_to_core),
+            breakable,
         }
-
-        if let Some(breakable) = breakable {
-            space = space.breakable(breakable);
-        }
-
-        space
     }
 }
 
 impl Quote {
-    fn into_core(self) -> decondenser::Quote {
-        let Self { opening, closing } = self;
-        decondenser::Quote::new(opening, closing)
+    fn into_core(self) -> QuoteConfig {
+        let Self {
+            opening,
+            closing,
+            concat_operator,
+        } = self;
+
+        QuoteConfig {
+            opening,
+            closing,
+            concat_operator,
+        }
     }
 }
 
@@ -126,6 +176,6 @@ impl Quote {
 /// integers. It's highly unlikely a value greater than ~100 will be used
 /// anywhere in the decondenser config. However, if someone tries to test the
 /// limits of the decondenser, it's okay to fallback to [`usize::MAX`].
-fn uint_to_core(value: u32) -> usize {
+pub(crate) fn uint_to_core(value: u32) -> usize {
     value.try_into().unwrap_or(usize::MAX)
 }
@@ -1,7 +1,10 @@
 mod into_core;
+mod utf16;
+
+use utf16::Utf16Offsets;
 
 use bindings::exports::decondenser as wit;
-use decondenser::Decondenser;
+use decondenser::{Decondenser, FormatError, SourceMap};
 
 /// The bindings are generated via `cargo component`:
 /// <https://github.com/bytecodealliance/cargo-component>
@@ -15,23 +18,151 @@ struct Component;
 bindings::export!(Component with_types_in bindings);
 
 impl wit::Guest for Component {
-    type Decondenser = Decondenser;
+    type Decondenser = Formatter;
 
     fn unescape(input: String) -> String {
         decondenser::unescape(&input).into_owned()
     }
 }
 
-impl wit::GuestDecondenser for Decondenser {
+/// The [`Decondenser`] with the settings of the component itself
+struct Formatter {
+    decondenser: Decondenser,
+
+    /// Whether to send the log messages to the host
+    log: bool,
+}
+
+impl Formatter {
+    /// The message is only built if the logging is enabled
+    fn log(&self, message: impl FnOnce() -> String) {
+        if self.log {
+            bindings::host::log(&message());
+        }
+    }
+
+    /// Logs the size of the input and the time it took to process it
+    fn logged<T>(&self, operation: &str, input: &str, run: impl FnOnce() -> T) -> T {
+        if !self.log {
+            return run();
+        }
+
+        let start = bindings::host::now();
+        let result = run();
+        let elapsed = bindings::host::now() - start;
+
+        self.log(|| format!("{operation}: {} bytes in {elapsed:.2}ms", input.len()));
+
+        result
+    }
+}
+
+impl wit::GuestDecondenser for Formatter {
     fn new(params: wit::DecondenserParams) -> Self {
-        params.into_decondenser()
+        let formatter = Self {
+            log: params.log.unwrap_or(false),
+            decondenser: params.into_decondenser(),
+        };
+
+        formatter.log(|| format!("new: {:?}", formatter.decondenser.to_config()));
+
+        formatter
     }
 
     fn format(&self, input: String) -> String {
-        eprintln!("EPRINTLN INVOKED");
-        println!("PRINTLN INVOKED");
-        // Delegate to the inherent method of the same name. No, it's not an
-        // unconditional recursion, the compiler would report it otherwise  .
-        self.format(&input)
+        self.logged("format", &input, || self.decondenser.format(&input))
     }
+
+    fn format_with_map(&self, input: String) -> wit::Formatted {
+        let (output, source_map) = self.logged("format-with-map", &input, || {
+            self.decondenser.format_with_map(&input)
+        });
+
+        wit::Formatted {
+            mappings: mappings_from_core(&input, &output, &source_map),
+            output,
+        }
+    }
+
+    fn format_range(&self, input: String, start: u32, end: u32) -> wit::TextEdit {
+        let offsets = Utf16Offsets::new(&input);
+        let range = offsets.to_utf8(start)..offsets.to_utf8(end);
+        let edit = self.logged("format-range", &input, || {
+            self.decondenser.format_range(&input, range)
+        });
+
+        wit::TextEdit {
+            start: offsets.to_utf16(edit.range.start),
+            end: offsets.to_utf16(edit.range.end),
+            new_text: edit.new_text,
+        }
+    }
+
+    fn format_detailed(&self, input: String) -> wit::FormattedDetailed {
+        let start = bindings::host::now();
+
+        let input_offsets = Utf16Offsets::new(&input);
+
+        let diagnostics = self
+            .decondenser
+            .diagnostics(&input)
+            .iter()
+            .filter_map(|err| diagnostic_from_core(&input_offsets, err))
+            .collect::<Vec<_>>();
+
+        let (output, source_map) = self.decondenser.format_with_map(&input);
+
+        let elapsed_ms = bindings::host::now() - start;
+
+        self.log(|| {
+            format!(
+                "format-detailed: {} bytes in {elapsed_ms:.2}ms, {} diagnostics",
+                input.len(),
+                diagnostics.len(),
+            )
+        });
+
+        wit::FormattedDetailed {
+            mappings: mappings_from_core(&input, &output, &source_map),
+            output,
+            diagnostics,
+            elapsed_ms,
+        }
+    }
+}
+
+fn mappings_from_core(input: &str, output: &str, source_map: &SourceMap) -> Vec<wit::Mapping> {
+    let input = Utf16Offsets::new(input);
+    let output = Utf16Offsets::new(output);
+
+    source_map
+        .mappings()
+        .iter()
+        .map(|mapping| wit::Mapping {
+            output_start: output.to_utf16(mapping.output.start),
+            output_end: output.to_utf16(mapping.output.end),
+            input_start: input.to_utf16(mapping.input.start),
+            input_end: input.to_utf16(mapping.input.end),
+        })
+        .collect()
+}
+
+/// Returns `None` for the errors that aren't problems of the input
+fn diagnostic_from_core(input: &Utf16Offsets, err: &FormatError) -> Option<wit::Diagnostic> {
+    let kind = match err {
+        FormatError::UnclosedGroup { .. } => wit::DiagnosticKind::UnclosedGroup,
+        FormatError::UnmatchedClosing { .. } => wit::DiagnosticKind::UnmatchedClosing,
+        FormatError::UnclosedQuote { .. } => wit::DiagnosticKind::UnclosedQuote,
+        FormatError::InvalidEscape { .. } => wit::DiagnosticKind::InvalidEscape,
+        _ => return None,
+    };
+
+    let range = err.range()?;
+
+    Some(wit::Diagnostic {
+        kind,
+        message: err.to_string(),
+        start: input.to_utf16(range.start),
+        end: input.to_utf16(range.end),
+    })
 }
@@ -0,0 +1,73 @@
+use crate::into_core::uint_to_core;
+
+/// Converts the offsets in the text between UTF-8 bytes used by the core and
+/// UTF-16 code units used by the strings in JS hosts.
+pub(crate) struct Utf16Offsets {
+    /// Ends of the non-ASCII chars of the text in both encodings. The ASCII
+    /// chars have the same size in both encodings, so they don't need to be
+    /// stored. The list is sorted by both offsets.
+    non_ascii_ends: Vec<Offset>,
+}
+
+#[derive(Clone, Copy)]
+struct Offset {
+    utf8: usize,
+    utf16: usize,
+}
+
+impl Offset {
+    /// How many more UTF-8 bytes than UTF-16 code units precede this offset
+    fn shift(self) -> usize {
+        self.utf8 - self.utf16
+    }
+}
+
+impl Utf16Offsets {
+    pub(crate) fn new(text: &str) -> Self {
+        let mut shift = 0;
+
+        let non_ascii_ends = text
+            .char_indices()
+            .filter(|(_, char)| !char.is_ascii())
+            .map(|(start, char)| {
+                shift += char.len_utf8() - char.len_utf16();
+                let utf8 = start + char.len_utf8();
+                Offset {
+                    utf8,
+                    utf16: utf8 - shift,
+                }
+            })
+            .collect();
+
+        Self { non_ascii_ends }
+    }
+
+    pub(crate) fn to_utf16(&self, utf8: usize) -> u32 {
+        let index = self.non_ascii_ends.partition_point(|end| end.utf8 <= utf8);
+        uint_from_core(utf8 - self.shift_before(index))
+    }
+
+    /// The offset inside of a surrogate pair resolves to the middle of the
+    /// char, which is fine for the core, since it only compares the offsets
+    /// with the token boundaries.
+    pub(crate) fn to_utf8(&self, utf16: u32) -> usize {
+        let utf16 = uint_to_core(utf16);
+        let index = self
+            .non_ascii_ends
+            .partition_point(|end| end.utf16 <= utf16);
+        utf16.saturating_add(self.shift_before(index))
+    }
+
+    fn shift_before(&self, index: usize) -> usize {
+        index
+            .checked_sub(1)
+            .map_or(0, |index| self.non_ascii_ends[index].shift())
+    }
+}
+
+/// Offsets are [`usize`] internally, but WIT spec supports only fixed-size
+/// integers. The input larger than 4GiB isn't realistic for the WASM
+/// component, so it's fine to saturate at [`u32::MAX`].
+fn uint_from_core(value: usize) -> u32 {
+    value.try_into().unwrap_or(u32::MAX)
+}
@@ -1,4 +1,6 @@
-//! Adapted from <https://github.com/nik-rev/simply-colored/blob/77b3c4c2df572494992cecb59d420e7e0569a8a9/src/lib.rs>
+//! Utilities for dealing with ANSI escape sequences.
+//!
+//! The constants are adapted from <https://github.com/nik-rev/simply-colored/blob/77b3c4c2df572494992cecb59d420e7e0569a8a9/src/lib.rs>
 #![expect(
     dead_code,
     reason = "
@@ -8,6 +10,44 @@
     "
 )]
 
+const ESC: char = '\x1b';
+
+/// Bell character. Terminates OSC sequences in the xterm dialect.
+const BEL: char = '\x07';
+
+/// Returns the length in bytes of the ANSI escape sequence at the start of the
+/// given string, or `None` if the string doesn't start with one.
+///
+/// Recognizes the following kinds of sequences:
+/// - CSI (`ESC [ <params> <intermediates> <final>`), e.g. `\x1b[31m`
+/// - OSC (`ESC ] <payload> <BEL | ESC \>`), e.g. hyperlinks `\x1b]8;;url\x07`
+/// - Two-character `Fe`/`Fs`/`Fp` escapes, e.g. `\x1b7`
+///
+/// Unterminated sequences are not considered escape sequences at all, so the
+/// text they consist of is handled as regular content.
+pub(crate) fn escape_sequence_len(str: &str) -> Option<usize> {
+    let mut chars = str.strip_prefix(ESC)?.char_indices();
+
+    // Byte offset relative to the string after the `ESC` character
+    let end = match chars.next()? {
+        (_, '[') => chars
+            .find(|(_, char)| !matches!(char, '\x20'..='\x3F'))
+            .filter(|(_, char)| matches!(char, '\x40'..='\x7E'))
+            .map(|(i, char)| i + char.len_utf8())?,
+        (_, ']') => loop {
+            match chars.next()? {
+                (i, BEL) => break i + BEL.len_utf8(),
+                (i, ESC) if chars.next()?.1 == '\\' => break i + ESC.len_utf8() + 1,
+                _ => {}
+            }
+        },
+        (i, char @ '\x30'..='\x7E') => i + char.len_utf8(),
+        _ => return None,
+    };
+
+    Some(ESC.len_utf8() + end)
+}
+
 /// Reset styling
 pub(crate) const RESET: &str = "\x1b[0m";
 
@@ -1,6 +1,6 @@
 use crate::sealed::Sealed;
 use crate::str::{IntoStr, Str};
-use crate::{IntoSpace, Space};
+use crate::{Indent, IntoSpace, Space};
 
 /// Describes a grouping of content delimited via opening and closing sequences
 /// (usually some kind of brackets).
@@ -12,6 +12,10 @@ pub struct Group {
     pub(crate) opening: Punct,
     pub(crate) closing: Punct,
     pub(crate) break_style: BreakStyle,
+    pub(crate) indent_style: IndentStyle,
+    pub(crate) indent: Option<Str>,
+    pub(crate) align_after: Option<Punct>,
+    pub(crate) max_align_padding: usize,
 }
 
 impl Group {
@@ -22,6 +26,10 @@ impl Group {
             opening,
             closing,
             break_style: BreakStyle::consistent(),
+            indent_style: IndentStyle::block(),
+            indent: None,
+            align_after: None,
+            max_align_padding: 16,
         }
     }
 
@@ -33,6 +41,62 @@ impl Group {
         self.break_style = value;
         self
     }
+
+    /// Sets the [`IndentStyle`] for this group. See its docs for more.
+    ///
+    /// Default is [`IndentStyle::block()`].
+    #[must_use]
+    pub fn indent_style(mut self, value: IndentStyle) -> Self {
+        self.indent_style = value;
+        self
+    }
+
+    /// String used to make a single level of indentation of the content of
+    /// this group. It doesn't affect the nested groups, that use their own
+    /// indent.
+    ///
+    /// Defaults to the global [`Decondenser::indent()`].
+    ///
+    /// [`Decondenser::indent()`]: crate::Decondenser::indent()
+    #[must_use]
+    pub fn indent(mut self, value: impl Indent) -> Self {
+        self.indent = Some(value.indent(Sealed));
+        self
+    }
+
+    /// Align the content that follows the first occurrence of the given
+    /// [`Punct`] on each line of the group into a column. Only takes effect if
+    /// the group has [`BreakStyle::consistent()`] and it is broken into
+    /// multiple lines:
+    ///
+    /// ```ignore
+    /// Person {
+    ///     name:    "Alice",
+    ///     address: "Wonderland",
+    /// }
+    /// ```
+    ///
+    /// The given [`Punct`] is recognized only directly inside of this group
+    /// in addition to the globally configured [`Decondenser::puncts()`].
+    ///
+    /// [`Decondenser::puncts()`]: crate::Decondenser::puncts()
+    #[must_use]
+    pub fn align_after(mut self, value: Punct) -> Self {
+        self.align_after = Some(value);
+        self
+    }
+
+    /// The maximum number of whitespace characters that can be inserted to
+    /// align the content with [`Group::align_after()`]. Lines where the content
+    /// before the punct is longer than on the shortest line by more than this
+    /// value don't participate in the alignment.
+    ///
+    /// Default is 16.
+    #[must_use]
+    pub fn max_align_padding(mut self, value: usize) -> Self {
+        self.max_align_padding = value;
+        self
+    }
 }
 
 /// Defines the algorithm used to decide whether to turn a space into a line
@@ -44,14 +108,22 @@ impl Group {
 ///
 /// Note that breaking is optional. It only takes place if the content of the
 /// group can not fit on a single line. If it does fit - it won't be broken
-/// disregarding the [`BreakStyle`].
-#[derive(Debug, Clone)]
+/// disregarding the [`BreakStyle`]. The only exception is
+/// [`BreakStyle::consistent_if_more_than()`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
 pub struct BreakStyle(pub(crate) BreakStyleEnum);
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
 pub(crate) enum BreakStyleEnum {
     Consistent,
     Compact,
+    Table,
+    Fill,
+    ConsistentIfMoreThan(usize),
 }
 
 impl BreakStyle {
@@ -85,9 +157,167 @@ impl BreakStyle {
     pub fn compact() -> Self {
         Self(BreakStyleEnum::Compact)
     }
+
+    /// Same as [`BreakStyle::compact()`], but an item that doesn't fit on the
+    /// rest of the line is moved to the next line as a whole before breaking
+    /// inside of it. With [`BreakStyle::compact()`] a nested group can start at
+    /// the end of the line and immediately break.
+    ///
+    /// ```ignore
+    /// [1, 2, 3, 4, 5, 6,
+    ///     Point { x: 1, y: 2 }, 7,
+    ///     8, 9]
+    /// ```
+    #[must_use]
+    pub fn fill() -> Self {
+        Self(BreakStyleEnum::Fill)
+    }
+
+    /// Same as [`BreakStyle::consistent()`], but if every item of the group is
+    /// a nested group of the same shape (same delimiters and the same
+    /// punctuation inside), then the fields of the nested groups are aligned
+    /// into columns. The nested groups may be prefixed with some content like a
+    /// type name, which is aligned as well.
+    ///
+    /// ```ignore
+    /// [
+    ///     Point { x: 1,  y: 2 },
+    ///     Point { x: 10, y: 200 },
+    ///     Point { x: 5,  y: 30 }
+    /// ]
+    /// ```
+    #[must_use]
+    pub fn table() -> Self {
+        Self(BreakStyleEnum::Table)
+    }
+
+    /// Same as [`BreakStyle::consistent()`], but the group is always broken if
+    /// it has more than the given number of items even if it fits on a single
+    /// line. The items are separated by the puncts that have a breakable space.
+    /// A group that is broken this way also breaks all groups that enclose it.
+    ///
+    /// This is useful for stable diffs of large lists, where adding an item
+    /// would otherwise reflow the entire list. With the threshold of `3`:
+    ///
+    /// ```ignore
+    /// foo(aaa, bbb, ccc);
+    /// foo(
+    ///     aaa,
+    ///     bbb,
+    ///     ccc,
+    ///     ddd
+    /// );
+    /// ```
+    #[must_use]
+    pub fn consistent_if_more_than(items: usize) -> Self {
+        Self(BreakStyleEnum::ConsistentIfMoreThan(items))
+    }
+}
+
+/// Defines how the content of the group is indented when the group is broken
+/// into multiple lines.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct IndentStyle(pub(crate) IndentStyleEnum);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub(crate) enum IndentStyleEnum {
+    Block,
+    Align,
+}
+
+impl IndentStyle {
+    /// The content starts on a new line after the opening punct, and it is
+    /// indented by a single level of [`Decondenser::indent()`] relative to the
+    /// line where the group starts.
+    ///
+    /// ```ignore
+    /// foo(
+    ///     aaa,
+    ///     bbb
+    /// );
+    /// ```
+    ///
+    /// [`Decondenser::indent()`]: crate::Decondenser::indent()
+    #[must_use]
+    pub fn block() -> Self {
+        Self(IndentStyleEnum::Block)
+    }
+
+    /// The content starts on the same line right after the opening punct, and
+    /// the following lines are aligned to the column where the content starts.
+    /// The closing punct stays on the same line as the last item.
+    ///
+    /// ```ignore
+    /// foo(aaa,
+    ///     bbb);
+    /// ```
+    #[must_use]
+    pub fn align() -> Self {
+        Self(IndentStyleEnum::Align)
+    }
 }
 
-/// Describes a quoted content that can not be broken into multiple lines.
+/// Defines the algorithm used to decide where to place line breaks in the
+/// output. See [`Decondenser::layout_algorithm()`] for more.
+///
+/// [`Decondenser::layout_algorithm()`]: crate::Decondenser::layout_algorithm()
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct LayoutAlgorithm(pub(crate) LayoutAlgorithmEnum);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub(crate) enum LayoutAlgorithmEnum {
+    Oppen,
+    Wadler,
+}
+
+impl LayoutAlgorithm {
+    /// Streaming algorithm based on the Oppen's pretty-printer. It decides
+    /// whether to break a group greedily as soon as it sees enough content to
+    /// know if the group fits on the current line. It uses memory that is
+    /// linear to the maximum size of the line.
+    #[must_use]
+    pub fn oppen() -> Self {
+        Self(LayoutAlgorithmEnum::Oppen)
+    }
+
+    /// Algorithm in the spirit of Wadler's "prettier printer" that buffers the
+    /// entire document and compares the costs of the layouts with and without
+    /// breaking each group that doesn't fit on the line. The cost is the number
+    /// of characters that overflow the [`max_line_size`] and then the number
+    /// of lines.
+    ///
+    /// Unlike [`LayoutAlgorithm::oppen()`], it can keep a group on a single
+    /// line and break only the nested group that is its only item if that
+    /// produces a shorter output. It also doesn't break the groups if that
+    /// doesn't reduce the overflow of the line:
+    ///
+    /// ```ignore
+    /// Some(Bar {
+    ///     ccc: 1,
+    ///     ddd: 2
+    /// })
+    /// ```
+    ///
+    /// The lookahead for such layouts is bounded by a few levels of nesting
+    /// to keep the formatting time reasonable.
+    ///
+    /// [`max_line_size`]: crate::Decondenser::max_line_size()
+    #[must_use]
+    pub fn wadler() -> Self {
+        Self(LayoutAlgorithmEnum::Wadler)
+    }
+}
+
+/// Describes a quoted content that can not be broken into multiple lines
+/// unless [`Quote::concat_operator()`] is configured.
 ///
 /// The content is delimited by the opening and closing sequences, and can
 /// contain special characters that are escaped using the provided escape
@@ -96,6 +326,10 @@ impl BreakStyle {
 pub struct Quote {
     pub(crate) opening: Str,
     pub(crate) closing: Str,
+
+    /// The closing delimiter followed by the concatenation operator, that ends
+    /// the line where the quoted content is split.
+    pub(crate) split_closing: Option<Str>,
 }
 
 impl Quote {
@@ -105,8 +339,44 @@ impl Quote {
         Self {
             opening: Str::new(opening),
             closing: Str::new(closing),
+            split_closing: None,
         }
     }
+
+    /// Allows splitting the quoted content that doesn't fit on the line into
+    /// several quoted literals concatenated with the given operator. The
+    /// content is split only after whitespace or an escaped newline, and the
+    /// continuation lines are indented.
+    ///
+    /// With the operator `+`:
+    ///
+    /// ```ignore
+    /// "Lorem ipsum dolor sit amet, " +
+    ///     "consectetur adipiscing elit"
+    /// ```
+    ///
+    /// Use `\` for the line continuation in shells or C macros, or an empty
+    /// string for languages where the adjacent literals are concatenated
+    /// implicitly like C or Python.
+    ///
+    /// The literals that are already concatenated with the operator are joined
+    /// if the formatter could have split them the same way, so they are laid
+    /// out the same way when the output is formatted again.
+    ///
+    /// Disabled by default.
+    #[must_use]
+    pub fn concat_operator(mut self, value: impl IntoStr) -> Self {
+        let operator = Str::new(value);
+
+        let split_closing = if operator.is_empty() {
+            self.closing.clone()
+        } else {
+            Str::new(format!("{} {operator}", self.closing))
+        };
+
+        self.split_closing = Some(split_closing);
+        self
+    }
 }
 
 /// The punctuation character. This would typically be a single character,
@@ -0,0 +1,474 @@
+use crate::config::{BreakStyle, Group, IndentStyle, LayoutAlgorithm, Punct, Quote};
+use crate::space::Space;
+use crate::visual_size::ArcVisualSize;
+use crate::{CharCount, Decondenser};
+
+/// Plain data representation of the [`Decondenser`] configuration.
+///
+/// Unlike the builder API, it can be inspected and compared, and with the
+/// `serde` cargo feature it can be serialized to share the configuration with
+/// the users of the CLI, for example.
+///
+/// Every field is optional. `None` keeps the value of the [`Decondenser`] the
+/// config is applied to via [`Decondenser::apply_config()`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(deny_unknown_fields))]
+pub struct DecondenserConfig {
+    /// See [`Decondenser::indent()`]
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub indent: Option<String>,
+
+    /// See [`Decondenser::tab_width()`]
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub tab_width: Option<usize>,
+
+    /// See [`Decondenser::max_line_size()`]
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub max_line_size: Option<usize>,
+
+    /// See [`Decondenser::no_break_size()`]
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub no_break_size: Option<usize>,
+
+    /// See [`Decondenser::visual_size()`]
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub visual_size: Option<VisualSizeConfig>,
+
+    /// See [`Decondenser::layout_algorithm()`]
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub layout_algorithm: Option<LayoutAlgorithm>,
+
+    /// See [`Decondenser::strict()`]
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub strict: Option<bool>,
+
+    /// See [`Decondenser::groups()`]
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub groups: Option<Vec<GroupConfig>>,
+
+    /// See [`Decondenser::puncts()`]
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub puncts: Option<Vec<PunctConfig>>,
+
+    /// See [`Decondenser::quotes()`]
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub quotes: Option<Vec<QuoteConfig>>,
+
+    /// See [`Decondenser::wrap_raw_after()`]
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub wrap_raw_after: Option<Vec<String>>,
+
+    /// See `Decondenser::escape_char()`
+    #[cfg(feature = "unstable")]
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub escape_char: Option<char>,
+
+    /// See `Decondenser::debug_layout()`
+    #[cfg(feature = "unstable")]
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub debug_layout: Option<bool>,
+
+    /// See `Decondenser::debug_indent()`
+    #[cfg(feature = "unstable")]
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub debug_indent: Option<bool>,
+}
+
+/// Built-in [`crate::VisualSize`] algorithms that can be represented in the
+/// [`DecondenserConfig`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum VisualSizeConfig {
+    /// [`CharCount`]
+    Chars,
+
+    /// `UnicodeWidth`
+    #[cfg(feature = "unicode-width")]
+    Width,
+
+    /// `GraphemeCount`
+    #[cfg(feature = "unicode-width")]
+    Graphemes,
+}
+
+/// Plain data representation of the [`Group`]. `None` means the default.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(deny_unknown_fields))]
+pub struct GroupConfig {
+    /// See [`Group::new()`]
+    pub opening: PunctConfig,
+
+    /// See [`Group::new()`]
+    pub closing: PunctConfig,
+
+    /// See [`Group::break_style()`]
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub break_style: Option<BreakStyle>,
+
+    /// See [`Group::indent_style()`]
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub indent_style: Option<IndentStyle>,
+
+    /// See [`Group::indent()`]
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub indent: Option<String>,
+
+    /// See [`Group::align_after()`]
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub align_after: Option<PunctConfig>,
+
+    /// See [`Group::max_align_padding()`]
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub max_align_padding: Option<usize>,
+}
+
+/// Plain data representation of the [`Punct`]. `None` means the default.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(deny_unknown_fields))]
+pub struct PunctConfig {
+    /// See [`Punct::new()`]
+    pub symbol: String,
+
+    /// See [`Punct::leading_space()`]
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub leading_space: Option<SpaceConfig>,
+
+    /// See [`Punct::trailing_space()`]
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub trailing_space: Option<SpaceConfig>,
+}
+
+/// Plain data representation of the [`Space`]. `None` means the default.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(deny_unknown_fields))]
+pub struct SpaceConfig {
+    /// The fixed size set via [`Space::size()`]
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub size: Option<usize>,
+
+    /// See [`Space::breakable()`]
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub breakable: Option<bool>,
+}
+
+/// Plain data representation of the [`Quote`]. `None` means the default.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(deny_unknown_fields))]
+pub struct QuoteConfig {
+    /// See [`Quote::new()`]
+    pub opening: String,
+
+    /// See [`Quote::new()`]
+    pub closing: String,
+
+    /// See [`Quote::concat_operator()`]
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub concat_operator: Option<String>,
+}
+
+impl Decondenser {
+    /// Exports the configuration of this [`Decondenser`]. Every field of the
+    /// returned config is set, except for the ones that are unset by default.
+    ///
+    /// A custom [`crate::VisualSize`] algorithm can't be represented in the
+    /// config, so it's exported as `None`. The same goes for the size of a
+    /// [`Space`] set to a range instead of a fixed number.
+    pub fn to_config(&self) -> DecondenserConfig {
+        DecondenserConfig {
+            indent: Some(self.indent.to_string()),
+            tab_width: Some(self.tab_width),
+            max_line_size: Some(self.max_line_size),
+            no_break_size: self.no_break_size,
+            visual_size: self.visual_size.config(),
+            layout_algorithm: Some(self.layout_algorithm.clone()),
+            strict: Some(self.strict),
+            groups: Some(self.groups.iter().map(Group::to_config).collect()),
+            puncts: Some(self.puncts.iter().map(Punct::to_config).collect()),
+            quotes: Some(self.quotes.iter().map(Quote::to_config).collect()),
+            wrap_raw_after: Some(
+                self.wrap_raw_after
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect(),
+            ),
+            #[cfg(feature = "unstable")]
+            escape_char: Some(self.escape_char),
+            #[cfg(feature = "unstable")]
+            debug_layout: Some(self.debug_layout),
+            #[cfg(feature = "unstable")]
+            debug_indent: Some(self.debug_indent),
+        }
+    }
+
+    /// Creates a [`Decondenser`] from the config applied on top of
+    /// [`Decondenser::empty()`]. It's the inverse of [`Decondenser::to_config()`].
+    pub fn from_config(config: DecondenserConfig) -> Self {
+        Self::empty().apply_config(config)
+    }
+
+    /// Overrides the configuration of this [`Decondenser`] with the fields of
+    /// the given config that are set. The lists of groups, puncts and quotes
+    /// are replaced entirely.
+    pub fn apply_config(mut self, config: DecondenserConfig) -> Self {
+        let DecondenserConfig {
+            indent,
+            tab_width,
+            max_line_size,
+            no_break_size,
+            visual_size,
+            layout_algorithm,
+            strict,
+            groups,
+            puncts,
+            quotes,
+            wrap_raw_after,
+            #[cfg(feature = "unstable")]
+            escape_char,
+            #[cfg(feature = "unstable")]
+            debug_layout,
+            #[cfg(feature = "unstable")]
+            debug_indent,
+        } = config;
+
+        if let Some(indent) = indent {
+            self = self.indent(indent);
+        }
+
+        if let Some(tab_width) = tab_width {
+            self = self.tab_width(tab_width);
+        }
+
+        if let Some(max_line_size) = max_line_size {
+            self = self.max_line_size(max_line_size);
+        }
+
+        if let Some(no_break_size) = no_break_size {
+            self = self.no_break_size(no_break_size);
+        }
+
+        if let Some(visual_size) = visual_size {
+            self.visual_size = ArcVisualSize::from_config(visual_size);
+        }
+
+        if let Some(layout_algorithm) = layout_algorithm {
+            self = self.layout_algorithm(layout_algorithm);
+        }
+
+        if let Some(strict) = strict {
+            self = self.strict(strict);
+        }
+
+        if let Some(groups) = groups {
+            self = self.groups(groups.into_iter().map(Group::from_config));
+        }
+
+        if let Some(puncts) = puncts {
+            self = self.puncts(puncts.into_iter().map(Punct::from_config));
+        }
+
+        if let Some(quotes) = quotes {
+            self = self.quotes(quotes.into_iter().map(Quote::from_config));
+        }
+
+        if let Some(wrap_raw_after) = wrap_raw_after {
+            self = self.wrap_raw_after(wrap_raw_after);
+        }
+
+        #[cfg(feature = "unstable")]
+        if let Some(escape_char) = escape_char {
+            self = self.escape_char(escape_char);
+        }
+
+        #[cfg(feature = "unstable")]
+        if let Some(debug_layout) = debug_layout {
+            self = self.debug_layout(debug_layout);
+        }
+
+        #[cfg(feature = "unstable")]
+        if let Some(debug_indent) = debug_indent {
+            self = self.debug_indent(debug_indent);
+        }
+
+        self
+    }
+}
+
+impl ArcVisualSize {
+    fn from_config(config: VisualSizeConfig) -> Self {
+        match config {
+            VisualSizeConfig::Chars => Self::new(CharCount),
+            #[cfg(feature = "unicode-width")]
+            VisualSizeConfig::Width => Self::new(crate::UnicodeWidth),
+            #[cfg(feature = "unicode-width")]
+            VisualSizeConfig::Graphemes => Self::new(crate::GraphemeCount),
+        }
+    }
+}
+
+impl Group {
+    /// Exports the configuration of this [`Group`]. See
+    /// [`Decondenser::to_config()`] for details.
+    pub fn to_config(&self) -> GroupConfig {
+        GroupConfig {
+            opening: self.opening.to_config(),
+            closing: self.closing.to_config(),
+            break_style: Some(self.break_style.clone()),
+            indent_style: Some(self.indent_style.clone()),
+            indent: self.indent.as_ref().map(ToString::to_string),
+            align_after: self.align_after.as_ref().map(Punct::to_config),
+            max_align_padding: Some(self.max_align_padding),
+        }
+    }
+
+    /// Creates a [`Group`] from the config. It's the inverse of
+    /// [`Group::to_config()`].
+    pub fn from_config(config: GroupConfig) -> Self {
+        let GroupConfig {
+            opening,
+            closing,
+            break_style,
+            indent_style,
+            indent,
+            align_after,
+            max_align_padding,
+        } = config;
+
+        let mut group = Self::new(Punct::from_config(opening), Punct::from_config(closing));
+
+        if let Some(break_style) = break_style {
+            group = group.break_style(break_style);
+        }
+
+        if let Some(indent_style) = indent_style {
+            group = group.indent_style(indent_style);
+        }
+
+        if let Some(indent) = indent {
+            group = group.indent(indent);
+        }
+
+        if let Some(align_after) = align_after {
+            group = group.align_after(Punct::from_config(align_after));
+        }
+
+        if let Some(max_align_padding) = max_align_padding {
+            group = group.max_align_padding(max_align_padding);
+        }
+
+        group
+    }
+}
+
+impl Punct {
+    /// Exports the configuration of this [`Punct`]. The spaces are exported
+    /// as `None` if they are the default ones.
+    pub fn to_config(&self) -> PunctConfig {
+        let space = |space: &Space| Some(space.to_config()).filter(|space| !space.is_default());
+
+        PunctConfig {
+            symbol: self.symbol.to_string(),
+            leading_space: space(&self.leading_space),
+            trailing_space: space(&self.trailing_space),
+        }
+    }
+
+    /// Creates a [`Punct`] from the config. It's the inverse of
+    /// [`Punct::to_config()`].
+    pub fn from_config(config: PunctConfig) -> Self {
+        let PunctConfig {
+            symbol,
+            leading_space,
+            trailing_space,
+        } = config;
+
+        let mut punct = Self::new(symbol);
+
+        if let Some(leading_space) = leading_space {
+            punct = punct.leading_space(Space::from_config(leading_space));
+        }
+
+        if let Some(trailing_space) = trailing_space {
+            punct = punct.trailing_space(Space::from_config(trailing_space));
+        }
+
+        punct
+    }
+}
+
+impl Space {
+    /// Exports the configuration of this [`Space`]. The size is exported only
+    /// if it's fixed.
+    pub fn to_config(&self) -> SpaceConfig {
+        let (min, max) = self.size;
+
+        SpaceConfig {
+            size: Some(min).filter(|_| min == max),
+            breakable: Some(self.breakable),
+        }
+    }
+
+    /// Creates a [`Space`] from the config. It's the inverse of
+    /// [`Space::to_config()`].
+    pub fn from_config(config: SpaceConfig) -> Self {
+        let SpaceConfig { size, breakable } = config;
+
+        let mut space = Self::new();
+
+        if let Some(size) = size {
+            space = space.size(size);
+        }
+
+        if let Some(breakable) = breakable {
+            space = space.breakable(breakable);
+        }
+
+        space
+    }
+}
+
+impl SpaceConfig {
+    fn is_default(&self) -> bool {
+        self.size.is_none() && !self.breakable.unwrap_or(false)
+    }
+}
+
+impl Quote {
+    /// Exports the configuration of this [`Quote`]
+    pub fn to_config(&self) -> QuoteConfig {
+        // The operator is separated from the closing delimiter with a space
+        let concat_operator = self.split_closing.as_ref().map(|split_closing| {
+            let operator = split_closing.strip_prefix(&*self.closing).unwrap_or("");
+            operator.strip_prefix(' ').unwrap_or(operator).to_owned()
+        });
+
+        QuoteConfig {
+            opening: self.opening.to_string(),
+            closing: self.closing.to_string(),
+            concat_operator,
+        }
+    }
+
+    /// Creates a [`Quote`] from the config. It's the inverse of
+    /// [`Quote::to_config()`].
+    pub fn from_config(config: QuoteConfig) -> Self {
+        let QuoteConfig {
+            opening,
+            closing,
+            concat_operator,
+        } = config;
+
+        let mut quote = Self::new(opening, closing);
+
+        if let Some(concat_operator) = concat_operator {
+            quote = quote.concat_operator(concat_operator);
+        }
+
+        quote
+    }
+}
@@ -23,6 +23,11 @@ impl<'a> Cursor<'a> {
         self.chars.clone().next()
     }
 
+    /// The remaining part of the input that wasn't consumed yet.
+    pub(crate) fn as_str(&self) -> &'a str {
+        self.chars.as_str()
+    }
+
     pub(crate) fn byte_offset(&self) -> usize {
         self.bytes - self.chars.as_str().len()
     }
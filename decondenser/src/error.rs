@@ -0,0 +1,123 @@
+use std::fmt;
+use std::ops::Range;
+
+/// Error returned from [`Decondenser::try_format()`].
+///
+/// The offsets are byte offsets in the input.
+///
+/// [`Decondenser::try_format()`]: crate::Decondenser::try_format()
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum FormatError {
+    /// The group isn't closed until the end of the input. Only reported in
+    /// the [`Decondenser::strict()`] mode and by [`Decondenser::diagnostics()`].
+    ///
+    /// [`Decondenser::strict()`]: crate::Decondenser::strict()
+    /// [`Decondenser::diagnostics()`]: crate::Decondenser::diagnostics()
+    UnclosedGroup {
+        /// Offset of the opening sequence of the group
+        offset: usize,
+
+        /// The opening sequence of the group
+        opening: String,
+    },
+
+    /// The closing sequence of a group doesn't match any opening sequence.
+    /// Only reported in the [`Decondenser::strict()`] mode and by
+    /// [`Decondenser::diagnostics()`].
+    ///
+    /// [`Decondenser::strict()`]: crate::Decondenser::strict()
+    /// [`Decondenser::diagnostics()`]: crate::Decondenser::diagnostics()
+    UnmatchedClosing {
+        /// Offset of the closing sequence
+        offset: usize,
+
+        /// The closing sequence of the group
+        closing: String,
+    },
+
+    /// The quoted content isn't closed until the end of the input. Only
+    /// reported in the [`Decondenser::strict()`] mode and by
+    /// [`Decondenser::diagnostics()`].
+    ///
+    /// [`Decondenser::strict()`]: crate::Decondenser::strict()
+    /// [`Decondenser::diagnostics()`]: crate::Decondenser::diagnostics()
+    UnclosedQuote {
+        /// Offset of the opening delimiter of the quote
+        offset: usize,
+
+        /// The opening delimiter of the quote
+        opening: String,
+    },
+
+    /// The escape sequence in the quoted content isn't recognized by
+    /// [`unescape()`]. Only reported in the [`Decondenser::strict()`] mode and
+    /// by [`Decondenser::diagnostics()`].
+    ///
+    /// [`unescape()`]: crate::unescape()
+    /// [`Decondenser::strict()`]: crate::Decondenser::strict()
+    /// [`Decondenser::diagnostics()`]: crate::Decondenser::diagnostics()
+    InvalidEscape {
+        /// Offset of the escape character
+        offset: usize,
+
+        /// The escape sequence as it is written in the input
+        escape: String,
+    },
+
+    /// The non-whitespace content of the output differs from the input. This
+    /// is always a bug in decondenser, so please report it.
+    ContentChanged {
+        /// Debug representation of the first differing token in the input
+        input: String,
+
+        /// Debug representation of the first differing token in the output
+        output: String,
+    },
+}
+
+impl FormatError {
+    /// The byte range of the problematic sequence in the input. Returns `None`
+    /// for [`FormatError::ContentChanged`], since it isn't a problem of the
+    /// input.
+    #[must_use]
+    pub fn range(&self) -> Option<Range<usize>> {
+        let (offset, sequence) = match self {
+            Self::UnclosedGroup { offset, opening } | Self::UnclosedQuote { offset, opening } => {
+                (offset, opening)
+            }
+            Self::UnmatchedClosing { offset, closing } => (offset, closing),
+            Self::InvalidEscape { offset, escape } => (offset, escape),
+            Self::ContentChanged { .. } => return None,
+        };
+
+        Some(*offset..offset + sequence.len())
+    }
+}
+
+impl fmt::Display for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnclosedGroup { offset, opening } => {
+                write!(f, "unclosed group `{opening}` at offset {offset}")
+            }
+            Self::UnmatchedClosing { offset, closing } => {
+                write!(f, "unmatched closing `{closing}` at offset {offset}")
+            }
+            Self::UnclosedQuote { offset, opening } => {
+                write!(f, "unclosed quote `{opening}` at offset {offset}")
+            }
+            Self::InvalidEscape { offset, escape } => {
+                write!(f, "invalid escape `{escape}` at offset {offset}")
+            }
+            Self::ContentChanged { input, output } => write!(
+                f,
+                "formatting changed the content of the input (this is a bug):\n\
+                input:  {input}\n\
+                output: {output}",
+            ),
+        }
+    }
+}
+
+impl std::error::Error for FormatError {}
@@ -0,0 +1,106 @@
+use super::measure::Measure;
+use crate::config::BreakStyleEnum as BreakStyle;
+use crate::parsing::l2::{Group, TokenTree};
+use crate::{Decondenser, Punct};
+
+/// Tracks the state of alignment of the content after [`Group::align_after`]
+/// punct inside of a single group.
+///
+/// The group content is split into items by any punct other than the aligned
+/// one. Only the first occurrence of the aligned punct in each item is padded.
+///
+/// [`Group::align_after`]: crate::Group::align_after()
+pub(super) struct Alignment<'i> {
+    punct: &'i Punct,
+
+    /// Paddings for each item that contains the aligned punct in order.
+    paddings: std::vec::IntoIter<usize>,
+
+    /// Whether the aligned punct was already seen in the current item.
+    aligned: bool,
+}
+
+impl<'i> Alignment<'i> {
+    pub(super) fn new(config: &Decondenser, group: &'i Group<'i>) -> Option<Self> {
+        let punct = group.config.align_after.as_ref()?;
+
+        if !matches!(
+            group.config.break_style.0,
+            BreakStyle::Consistent | BreakStyle::ConsistentIfMoreThan(_)
+        ) {
+            return None;
+        }
+
+        let measure = Measure::new(config);
+
+        let mut key_sizes = vec![];
+        let mut key_size = 0;
+        let mut pending_space = 0;
+        let mut aligned = false;
+
+        for token in &group.content {
+            match token {
+                TokenTree::Punct(item) if std::ptr::eq(item.config, punct) => {
+                    if !aligned {
+                        key_sizes.push(key_size);
+                        aligned = true;
+                    }
+                }
+                TokenTree::Punct(_) => {
+                    key_size = 0;
+                    pending_space = 0;
+                    aligned = false;
+                }
+                TokenTree::Space(_) | TokenTree::Newline(_) => {
+                    // Leading and trailing spaces of the key are controlled
+                    // by the puncts that surround it.
+                    if key_size > 0 {
+                        pending_space = 1;
+                    }
+                }
+                _ => {
+                    key_size += pending_space + measure.token(token);
+                    pending_space = 0;
+                }
+            }
+        }
+
+        // Keys that would require too much padding for the shortest key are
+        // considered outliers, and they don't participate in the alignment.
+        let min_key_size = key_sizes.iter().copied().min().unwrap_or(0);
+        let max_key_size = min_key_size + group.config.max_align_padding;
+
+        let target_size = key_sizes
+            .iter()
+            .copied()
+            .filter(|&size| size <= max_key_size)
+            .max()
+            .unwrap_or(0);
+
+        // Turn key sizes into paddings in-place
+        for size in &mut key_sizes {
+            *size = target_size.saturating_sub(*size);
+        }
+
+        Some(Self {
+            punct,
+            paddings: key_sizes.into_iter(),
+            aligned: false,
+        })
+    }
+
+    /// Returns the padding that should follow the given punct if any.
+    pub(super) fn on_punct(&mut self, punct: &Punct) -> Option<usize> {
+        if !std::ptr::eq(punct, self.punct) {
+            self.aligned = false;
+            return None;
+        }
+
+        if self.aligned {
+            return None;
+        }
+
+        self.aligned = true;
+        self.paddings.next()
+    }
+}
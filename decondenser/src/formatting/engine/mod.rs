@@ -23,15 +23,24 @@
 //!
 //! Also, this blog post by @mcyoung is a great resource for understanding:
 //! <https://mcyoung.xyz/2025/03/11/formatters/>
+//!
+//! An alternative [`wadler`] engine that lays out the entire document at once
+//! to choose the cheapest layout can be selected with the
+//! [`LayoutAlgorithm`](crate::LayoutAlgorithm) config.
 
 mod normalized;
 mod printer;
 mod sliding_deque;
 mod token;
+mod wadler;
 
 pub(crate) use crate::visual_size::MeasuredStr;
 
 use self::normalized::NormalizedFormatter;
+use self::printer::PrinterConfig;
+use self::wadler::WadlerFormatter;
+use crate::SourceMap;
+use crate::config::LayoutAlgorithmEnum as LayoutAlgorithm;
 use crate::formatting::BreakStyle;
 
 /// A generic formatter that works in terms of groups, raw strings, spaces,
@@ -42,8 +51,8 @@ use crate::formatting::BreakStyle;
 ///
 /// See the [`NormalizedFormatter`] for more details on the core algorithm.
 pub(crate) struct Formatter<'a> {
-    /// [`NormalizedFormatter`] expects a normalized sequence of tokens.
-    fmt: NormalizedFormatter<'a>,
+    /// [`Engine`] expects a normalized sequence of tokens.
+    fmt: Box<dyn Engine<'a> + 'a>,
 
     /// The normalized indentation of the current pending block of tokens.
     indent: isize,
@@ -52,7 +61,84 @@ pub(crate) struct Formatter<'a> {
     blank: Blank,
 
     /// The normalized [`Control`] of the current pending block of tokens.
-    control: Vec<Control>,
+    control: Vec<Control<'a>>,
+
+    /// The alignment paddings added to the [`Blank`] of the current pending
+    /// block of tokens. Each padding is stored along with the number of groups
+    /// above the innermost one whose break state decides if it's printed.
+    paddings: Vec<(usize, usize)>,
+}
+
+/// The layout algorithm that decides where to place line breaks. It receives
+/// the normalized sequence of tokens from the [`Formatter`].
+trait Engine<'a> {
+    /// The `indent` is the string used to make a single level of indentation
+    /// of the content of the group.
+    fn begin(&mut self, break_style: BreakStyle, indent: MeasuredStr<'a>);
+    fn end(&mut self);
+
+    /// Breaks the innermost group that isn't closed and all groups that
+    /// enclose it regardless of whether they fit on the line.
+    fn force_break(&mut self);
+
+    fn indent(&mut self, diff: isize);
+
+    /// Aligns the lines that follow to the column where the next raw content is
+    /// printed until the matching [`Engine::align_end`].
+    fn align_begin(&mut self);
+    fn align_end(&mut self);
+    fn hard_break(&mut self, size: usize);
+    fn soft_break(&mut self);
+
+    /// Same as [`Engine::soft_break`], but it replaces a line break from the
+    /// input. If it doesn't take place, then it's lost in the output.
+    fn newline(&mut self);
+
+    /// Same as [`Engine::soft_break`], but the given text is printed around the
+    /// line break if it takes place.
+    fn split(&mut self, text: BreakText<'a>);
+
+    fn space(&mut self, size: usize);
+    fn padding(&mut self, size: usize, depth: usize);
+    fn raw(&mut self, content: MeasuredStr<'a>);
+
+    /// End of input
+    fn eoi(self: Box<Self>) -> (String, SourceMap);
+}
+
+impl<'a> dyn Engine<'a> + 'a {
+    fn control(&mut self, control: Control<'a>) {
+        match control {
+            Control::SoftBreak => self.soft_break(),
+            Control::Newline => self.newline(),
+            Control::Split(text) => self.split(text),
+            Control::Begin(break_style, indent) => self.begin(break_style, indent),
+            Control::ForceBreak => self.force_break(),
+            Control::End => self.end(),
+        }
+    }
+}
+
+/// Position of the output in the surrounding document. It's non-default only
+/// when formatting a part of a bigger document.
+#[derive(Debug, Default, Clone, Copy)]
+pub(crate) struct Placement<'a> {
+    /// Column where the first line of the output starts
+    pub(crate) start_column: usize,
+
+    /// Printed at the start of every line of the output after the first one
+    pub(crate) line_indent: MeasuredStr<'a>,
+}
+
+/// Text printed around the line break only if the break takes place. Used to
+/// split the content that can't contain line breaks as is.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct BreakText<'a> {
+    /// Printed at the end of the line before the break
+    pub(crate) before: MeasuredStr<'a>,
+
+    /// Printed at the start of the next line after the indent
+    pub(crate) after: MeasuredStr<'a>,
 }
 
 enum Blank {
@@ -66,28 +152,46 @@ impl Default for Blank {
     }
 }
 
-enum Control {
+#[derive(Clone, Copy)]
+enum Control<'a> {
     SoftBreak,
-    Begin(BreakStyle),
+    Newline,
+    Split(BreakText<'a>),
+    Begin(BreakStyle, MeasuredStr<'a>),
+    ForceBreak,
     End,
 }
 
 impl<'a> Formatter<'a> {
-    pub(crate) fn new(config: &'a crate::Decondenser) -> Self {
+    pub(crate) fn new(config: &'a crate::Decondenser, placement: Placement<'a>) -> Self {
+        let printer = PrinterConfig::new(config, placement);
+
+        let fmt: Box<dyn Engine<'a>> = match config.layout_algorithm.0 {
+            LayoutAlgorithm::Oppen => Box::new(NormalizedFormatter::new(printer)),
+            LayoutAlgorithm::Wadler => Box::new(WadlerFormatter::new(printer)),
+        };
+
         Self {
-            fmt: NormalizedFormatter::new(config),
+            fmt,
             indent: 0,
             blank: Blank::default(),
             control: vec![],
+            paddings: vec![],
         }
     }
 
-    pub(crate) fn begin(&mut self, break_style: BreakStyle) {
-        self.control.push(Control::Begin(break_style));
+    pub(crate) fn begin(&mut self, break_style: BreakStyle, indent: MeasuredStr<'a>) {
+        self.control.push(Control::Begin(break_style, indent));
+    }
+
+    /// Breaks the current group and all groups that enclose it even if they
+    /// fit on the line.
+    pub(crate) fn force_break(&mut self) {
+        self.control.push(Control::ForceBreak);
     }
 
     pub(crate) fn end(&mut self) {
-        if let Some(Control::Begin(_)) = self.control.last() {
+        if let Some(Control::Begin(..)) = self.control.last() {
             self.control.pop();
         } else {
             self.control.push(Control::End);
@@ -95,12 +199,38 @@ impl<'a> Formatter<'a> {
     }
 
     pub(crate) fn indent(&mut self, diff: isize) {
+        self.flush_ends();
         self.indent += diff;
     }
 
+    /// Indents the following lines to the column where the next raw content is
+    /// printed instead of the indent levels until [`Formatter::align_end`].
+    pub(crate) fn align_begin(&mut self) {
+        self.flush_ends();
+        self.fmt.align_begin();
+    }
+
+    pub(crate) fn align_end(&mut self) {
+        self.flush_ends();
+        self.fmt.align_end();
+    }
+
     pub(crate) fn soft_break(&mut self) {
-        if let Some(Control::SoftBreak) = self.control.last() {
-            // Avoid consecutive soft breaks
+        self.push_soft_break(Control::SoftBreak);
+    }
+
+    /// A soft break in place of a line break from the input
+    pub(crate) fn newline(&mut self) {
+        self.push_soft_break(Control::Newline);
+    }
+
+    fn push_soft_break(&mut self, control: Control<'a>) {
+        if let Some(last @ (Control::SoftBreak | Control::Newline)) = self.control.last_mut() {
+            // Avoid consecutive soft breaks. The regular soft break stays in
+            // the output even if it doesn't take place, so it wins.
+            if let Control::SoftBreak = control {
+                *last = control;
+            }
             return;
         }
 
@@ -109,7 +239,15 @@ impl<'a> Formatter<'a> {
             return;
         }
 
-        self.control.push(Control::SoftBreak);
+        self.control.push(control);
+    }
+
+    /// A soft break inside of the content that is otherwise unbreakable. The
+    /// `before` and `after` text is printed around the line break only if it
+    /// takes place.
+    pub(crate) fn split(&mut self, before: MeasuredStr<'a>, after: MeasuredStr<'a>) {
+        self.control
+            .push(Control::Split(BreakText { before, after }));
     }
 
     pub(crate) fn space(&mut self, size: usize) {
@@ -126,6 +264,17 @@ impl<'a> Formatter<'a> {
         }
     }
 
+    /// Extra space that is added after the preceding space only if the group
+    /// `depth` levels above the enclosing one is broken. Used for alignment.
+    pub(crate) fn padding(&mut self, size: usize, depth: usize) {
+        // Paddings of different depths are independent, because each of them
+        // depends on the break state of its own group
+        match self.paddings.iter_mut().find(|(_, d)| *d == depth) {
+            Some((current_size, _)) => *current_size += size,
+            None => self.paddings.push((size, depth)),
+        }
+    }
+
     #[expect(
         dead_code,
         reason = "
@@ -149,7 +298,7 @@ impl<'a> Formatter<'a> {
             Blank::Space(_) => {
                 // No soft breaks are needed adjacently to a hard break
                 self.control
-                    .retain(|control| !matches!(control, Control::SoftBreak));
+                    .retain(|control| !matches!(control, Control::SoftBreak | Control::Newline));
 
                 self.blank = Blank::HardBreak(size);
             }
@@ -157,9 +306,10 @@ impl<'a> Formatter<'a> {
     }
 
     /// End of input
-    pub(crate) fn eoi(mut self) -> String {
+    pub(crate) fn eoi(mut self) -> (String, SourceMap) {
         // Strip trailing whitespace/newlines from the output
         self.blank = Blank::default();
+        self.paddings.clear();
         self.flush_normalized_tokens();
         self.fmt.eoi()
     }
@@ -173,18 +323,64 @@ impl<'a> Formatter<'a> {
         self.fmt.raw(content);
     }
 
+    /// Closes the groups that ended before the indent change, so that the
+    /// indent change of the parent group doesn't get inside of the nested
+    /// group. The controls that precede the last `End` are flushed along with
+    /// it. For example, an unclosed group may end right after a soft break.
+    fn flush_ends(&mut self) {
+        let Some(last_end) = self
+            .control
+            .iter()
+            .rposition(|control| matches!(control, Control::End))
+        else {
+            return;
+        };
+
+        if self.indent != 0 {
+            self.fmt.indent(self.indent);
+            self.indent = 0;
+        }
+
+        self.flush_paddings();
+
+        for control in self.control.drain(..=last_end) {
+            self.fmt.control(control);
+        }
+    }
+
+    fn flush_paddings(&mut self) {
+        for (size, depth) in self.paddings.drain(..) {
+            if size > 0 {
+                self.fmt.padding(size, depth);
+            }
+        }
+    }
+
     fn flush_normalized_tokens(&mut self) {
         if self.indent != 0 {
             self.fmt.indent(self.indent);
             self.indent = 0;
         }
 
+        // The paddings must be flushed before any `Begin` controls, because
+        // they are applied based on the break state of the groups where they
+        // were added
+        self.flush_paddings();
+
+        // The blank is placed before the groups that begin right after it, so
+        // that the space which is skipped at the start of a new line doesn't
+        // count towards the size of the group
+        let begins = self
+            .control
+            .iter()
+            .rev()
+            .take_while(|control| matches!(control, Control::Begin(..)))
+            .count();
+
+        let begins = self.control.split_off(self.control.len() - begins);
+
         for control in self.control.drain(..) {
-            match control {
-                Control::SoftBreak => self.fmt.soft_break(),
-                Control::Begin(break_style) => self.fmt.begin(break_style),
-                Control::End => self.fmt.end(),
-            }
+            self.fmt.control(control);
         }
 
         match std::mem::take(&mut self.blank) {
@@ -192,5 +388,9 @@ impl<'a> Formatter<'a> {
             Blank::HardBreak(size) if size > 0 => self.fmt.hard_break(size),
             _ => {}
         }
+
+        for control in begins {
+            self.fmt.control(control);
+        }
     }
 }
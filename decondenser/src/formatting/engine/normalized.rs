@@ -1,7 +1,8 @@
 use super::printer::{Printer, PrinterConfig};
 use super::sliding_deque::SlidingDeque;
 use super::token::{Measurement, Size, Token};
-use crate::Decondenser;
+use super::{BreakText, Engine};
+use crate::SourceMap;
 use crate::formatting::BreakStyle;
 use crate::utils::debug_panic;
 use crate::visual_size::MeasuredStr;
@@ -30,6 +31,9 @@ pub(super) struct NormalizedFormatter<'a> {
     /// The size is calculated as if the tokens were printed on a single line.
     printed_single_line_size: usize,
 
+    /// Break styles of the groups that are not yet closed
+    groups: Vec<BreakStyle>,
+
     /// Size of all [`Self::tokens`] plus the ones that are already printed.
     /// This is guaranteed to be >= [`Self::printed_single_line_size`].
     ///
@@ -68,77 +72,30 @@ impl<'a> Tokens<'a> {
 }
 
 impl<'a> NormalizedFormatter<'a> {
-    pub(super) fn new(config: &'a Decondenser) -> Self {
+    pub(super) fn new(config: PrinterConfig<'a>) -> Self {
         NormalizedFormatter {
             tokens: Tokens {
                 deque: SlidingDeque::new(),
                 unmeasured: VecDeque::new(),
             },
             printed_single_line_size: 0,
+            groups: vec![],
             total_single_line_size: 0,
-            printer: Printer::new(PrinterConfig {
-                max_line_size: config.max_line_size,
-                no_break_size: config.no_break_size.unwrap_or(config.max_line_size / 2),
-                debug_layout: config.debug_layout,
-                debug_indent: config.debug_indent,
-                indent_str: config.visual_size.measured_str(&config.indent),
-            }),
-        }
-    }
-
-    /// End of input
-    pub(super) fn eoi(mut self) -> String {
-        if !self.tokens.unmeasured.is_empty() {
-            self.measure_tokens();
-            self.print_measured_tokens();
+            printer: Printer::new(config),
         }
-        self.printer.finish()
-    }
-
-    pub(super) fn begin(&mut self, break_style: BreakStyle) {
-        self.tokens.push_unmeasured(Token::Begin {
-            break_style,
-            next_break_distance: Measurement::Unmeasured {
-                preceding_tokens_size: self.total_single_line_size,
-            },
-        });
-    }
-
-    pub(super) fn indent(&mut self, diff: isize) {
-        self.tokens.push(Token::Indent(diff));
-    }
-
-    pub(super) fn end(&mut self) {
-        self.tokens.push_unmeasured(Token::End);
-    }
-
-    pub(super) fn hard_break(&mut self, size: usize) {
-        self.measure_tokens();
-        self.break_while(|_| true);
-        self.printer.hard_break(size);
     }
 
-    pub(super) fn soft_break(&mut self) {
-        self.measure_tokens();
+    fn push_soft_break(&mut self, text: Option<BreakText<'a>>) {
+        self.measure_tokens(text.map_or(0, |text| text.before.visual_size()));
         self.tokens.push_unmeasured(Token::SoftBreak {
             next_break_distance: Measurement::Unmeasured {
                 preceding_tokens_size: self.total_single_line_size,
             },
+            fill: self.groups.last() == Some(&BreakStyle::Fill),
+            text,
         });
     }
 
-    pub(super) fn space(&mut self, size: usize) {
-        self.tokens.push(Token::Space(size));
-        self.total_single_line_size += size;
-        self.break_while_overflows();
-    }
-
-    pub(super) fn raw(&mut self, content: MeasuredStr<'a>) {
-        self.tokens.push(Token::Raw(content));
-        self.total_single_line_size += content.visual_size();
-        self.break_while_overflows();
-    }
-
     fn break_while_overflows(&mut self) {
         self.break_while(|fmt| {
             let pending_size = fmt.total_single_line_size - fmt.printed_single_line_size;
@@ -198,22 +155,30 @@ impl<'a> NormalizedFormatter<'a> {
                 }
                 Token::SoftBreak {
                     next_break_distance,
+                    text,
+                    ..
                 } => {
                     let Measurement::Measured(distance) = next_break_distance else {
                         return;
                     };
-                    self.printer.soft_break(distance);
+                    self.printer.soft_break(distance, text);
                 }
                 Token::Begin {
                     next_break_distance,
                     break_style,
+                    indent,
+                    forced,
                 } => {
                     let Measurement::Measured(distance) = next_break_distance else {
                         return;
                     };
-                    self.printer.begin(break_style, distance);
+                    let distance = if forced { Size::Infinite } else { distance };
+                    self.printer.begin(break_style, indent, distance);
                 }
+                Token::Padding { size, depth } => self.printer.padding(size, depth),
                 Token::Indent(diff) => self.printer.indent(diff),
+                Token::AlignBegin => self.printer.align_begin(),
+                Token::AlignEnd => self.printer.align_end(),
                 Token::End => {
                     if self.tokens.starts_with_unmeasured() {
                         // This `End` is still staged for its group measurement,
@@ -229,8 +194,16 @@ impl<'a> NormalizedFormatter<'a> {
         }
     }
 
-    fn measure_tokens(&mut self) {
+    /// The `line_end` is the size of the text that ends the line if the new
+    /// break that triggered the measurement takes place.
+    fn measure_tokens(&mut self, line_end: usize) {
+        let planned_size = self.total_single_line_size + line_end;
+
         let mut depth: usize = 0;
+
+        // Whether the measurement started inside of a group that isn't closed
+        let mut in_open_group = false;
+
         let mut cursor = self.tokens.unmeasured.len();
 
         while let Some(new_cursor) = cursor.checked_sub(1) {
@@ -263,12 +236,13 @@ impl<'a> NormalizedFormatter<'a> {
                         // the block, that marks the end of measurement of the
                         // previous break of the parent block if there is one.
                         if cursor + 1 == self.tokens.unmeasured.len() {
+                            in_open_group = true;
                             continue;
                         }
                         return;
                     }
                     remove_unmeasured();
-                    next_break_distance.measure_from(self.total_single_line_size);
+                    next_break_distance.measure_from(planned_size);
                     depth -= 1;
                 }
                 Token::End => {
@@ -277,14 +251,27 @@ impl<'a> NormalizedFormatter<'a> {
                 }
                 Token::SoftBreak {
                     next_break_distance,
+                    fill,
+                    ..
                 } => {
+                    // The break of the fill group is measured only by the
+                    // break on the same level after the nested group ends
+                    if *fill && in_open_group && depth == 0 {
+                        return;
+                    }
+
                     remove_unmeasured();
-                    next_break_distance.measure_from(self.total_single_line_size);
+                    next_break_distance.measure_from(planned_size);
                     if depth == 0 {
                         return;
                     }
                 }
-                Token::Raw(_) | Token::Space(_) | Token::Indent(_) => {
+                Token::Raw(_)
+                | Token::Space(_)
+                | Token::Padding { .. }
+                | Token::Indent(_)
+                | Token::AlignBegin
+                | Token::AlignEnd => {
                     debug_panic!(
                         "This token should never have been part of unmeasured \
                         token indices: {token:?}"
@@ -295,6 +282,97 @@ impl<'a> NormalizedFormatter<'a> {
     }
 }
 
+impl<'a> Engine<'a> for NormalizedFormatter<'a> {
+    fn eoi(mut self: Box<Self>) -> (String, SourceMap) {
+        if !self.tokens.unmeasured.is_empty() {
+            self.measure_tokens(0);
+            self.print_measured_tokens();
+        }
+        self.printer.finish()
+    }
+
+    fn begin(&mut self, break_style: BreakStyle, indent: MeasuredStr<'a>) {
+        self.groups.push(break_style);
+        self.tokens.push_unmeasured(Token::Begin {
+            break_style,
+            indent,
+            next_break_distance: Measurement::Unmeasured {
+                preceding_tokens_size: self.total_single_line_size,
+            },
+            forced: false,
+        });
+    }
+
+    fn force_break(&mut self) {
+        // The `Begin` tokens of the groups that are not closed yet are never
+        // measured, so they are all still in the unmeasured indices. Walk them
+        // backwards skipping the groups that are already closed.
+        let mut depth: usize = 0;
+
+        for &index in self.tokens.unmeasured.iter().rev() {
+            match self.tokens.deque.get_mut(index) {
+                Some(Token::End) => depth += 1,
+                Some(Token::Begin { forced, .. }) => match depth.checked_sub(1) {
+                    Some(new_depth) => depth = new_depth,
+                    None => *forced = true,
+                },
+                _ => {}
+            }
+        }
+    }
+
+    fn indent(&mut self, diff: isize) {
+        self.tokens.push(Token::Indent(diff));
+    }
+
+    fn align_begin(&mut self) {
+        self.tokens.push(Token::AlignBegin);
+    }
+
+    fn align_end(&mut self) {
+        self.tokens.push(Token::AlignEnd);
+    }
+
+    fn end(&mut self) {
+        self.groups.pop();
+        self.tokens.push_unmeasured(Token::End);
+    }
+
+    fn hard_break(&mut self, size: usize) {
+        self.measure_tokens(0);
+        self.break_while(|_| true);
+        self.printer.hard_break(size);
+    }
+
+    fn soft_break(&mut self) {
+        self.push_soft_break(None);
+    }
+
+    fn newline(&mut self) {
+        self.push_soft_break(None);
+    }
+
+    fn split(&mut self, text: BreakText<'a>) {
+        self.push_soft_break(Some(text));
+    }
+
+    fn space(&mut self, size: usize) {
+        self.tokens.push(Token::Space(size));
+        self.total_single_line_size += size;
+        self.break_while_overflows();
+    }
+
+    fn padding(&mut self, size: usize, depth: usize) {
+        self.tokens.push(Token::Padding { size, depth });
+    }
+
+    fn raw(&mut self, content: MeasuredStr<'a>) {
+        self.tokens.push(Token::Raw(content));
+        self.total_single_line_size += content.visual_size();
+        self.break_while_overflows();
+    }
+}
+
 impl fmt::Debug for SlidingDeque<Token<'_>> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let basis = self.basis();
@@ -1,7 +1,9 @@
 use super::token::Size;
+use super::{BreakText, Placement};
+use crate::SourceMap;
 use crate::formatting::BreakStyle;
 use crate::utils::debug_panic;
-use crate::visual_size::MeasuredStr;
+use crate::visual_size::{ArcVisualSize, MeasuredStr};
 use std::{cmp, iter};
 
 #[derive(Debug)]
@@ -9,6 +11,9 @@ pub(super) struct Printer<'a> {
     /// Output string being built
     output: String,
 
+    /// Offsets of the content in the [`Self::output`] copied from the input
+    source_map: SourceMap,
+
     /// Spare budget of size left on the current line.
     ///
     /// Can be zero if the last printed token was >= in size than the
@@ -18,19 +23,46 @@ pub(super) struct Printer<'a> {
     /// some token, which is not allowed.
     line_size_budget: usize,
 
-    /// Level of indentation for the current line
-    indent_level: usize,
+    /// Visual size of the current line printed so far including indentation.
+    column: usize,
+
+    /// Levels of indentation for the current line. Each level is made with
+    /// the indent string of the group where it was added.
+    indent: Vec<MeasuredStr<'a>>,
+
+    /// Stack of alignments-in-progress nested one inside another
+    alignments: Vec<Alignment>,
+
+    /// Whether the alignment starts at the column of the next printed content
+    pending_alignment: bool,
 
     /// Stack of groups-in-progress nested one inside another
     groups_stack: Vec<Group>,
 
+    /// Strings used to make a single level of indentation of the content of
+    /// each group in the [`Self::groups_stack`]
+    groups_indents: Vec<MeasuredStr<'a>>,
+
     spaces: Spaces,
 
+    /// Alignment padding that is printed together with the pending [`Spaces`]
+    padding: usize,
+
     /// Constant values intentionally separated out of the struct to group them
     /// together for readability. Everything else in this struct is mutable.
     config: PrinterConfig<'a>,
 }
 
+#[derive(Debug)]
+struct Alignment {
+    /// Column where the aligned lines start
+    column: usize,
+
+    /// Number of indentation levels at the start of the alignment. Only the
+    /// levels above it are added on top of the aligned column.
+    indent_level: usize,
+}
+
 #[derive(Debug, Clone)]
 enum Group {
     /// The group fits on the current line. The [`BreakStyle`] is stored in
@@ -50,15 +82,39 @@ impl Group {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub(super) struct PrinterConfig<'a> {
     pub(super) max_line_size: usize,
     pub(super) no_break_size: usize,
     pub(super) debug_layout: bool,
     pub(super) debug_indent: bool,
 
-    /// String used to make a single level of indentation.
+    /// String used to make a single level of indentation outside of any group.
     pub(super) indent_str: MeasuredStr<'a>,
+
+    pub(super) placement: Placement<'a>,
+
+    /// Used to measure the exact size of the content with tabs, which depends
+    /// on the column where the content is printed.
+    pub(super) visual_size: &'a ArcVisualSize,
+    pub(super) tab_width: usize,
+}
+
+impl<'a> PrinterConfig<'a> {
+    pub(super) fn new(config: &'a crate::Decondenser, placement: Placement<'a>) -> Self {
+        Self {
+            max_line_size: config.max_line_size,
+            no_break_size: config.no_break_size.unwrap_or(config.max_line_size / 2),
+            debug_layout: config.debug_layout,
+            debug_indent: config.debug_indent,
+            indent_str: config
+                .visual_size
+                .measured_str(&config.indent, config.tab_width),
+            placement,
+            visual_size: &config.visual_size,
+            tab_width: config.tab_width,
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -71,10 +127,21 @@ impl<'a> Printer<'a> {
     pub(super) fn new(config: PrinterConfig<'a>) -> Self {
         Self {
             output: String::new(),
-            line_size_budget: cmp::max(config.max_line_size, config.no_break_size),
-            indent_level: 0,
+            source_map: SourceMap::default(),
+            line_size_budget: cmp::max(
+                config
+                    .max_line_size
+                    .saturating_sub(config.placement.start_column),
+                config.no_break_size,
+            ),
+            column: config.placement.start_column,
+            indent: Vec::new(),
+            alignments: Vec::new(),
+            pending_alignment: false,
             groups_stack: Vec::new(),
+            groups_indents: Vec::new(),
             spaces: Spaces::Skip,
+            padding: 0,
             config,
         }
     }
@@ -83,11 +150,18 @@ impl<'a> Printer<'a> {
         self.line_size_budget = self.line_size_budget.saturating_sub(size);
     }
 
-    pub(super) fn begin(&mut self, break_style: BreakStyle, next_space_distance: Size) {
+    pub(super) fn begin(
+        &mut self,
+        break_style: BreakStyle,
+        indent: MeasuredStr<'a>,
+        next_space_distance: Size,
+    ) {
         if self.config.debug_layout {
             self.output.push(match break_style {
-                BreakStyle::Consistent => '«',
+                BreakStyle::Consistent | BreakStyle::ConsistentIfMoreThan(_) => '«',
                 BreakStyle::Compact => '‹',
+                BreakStyle::Table => '⟦',
+                BreakStyle::Fill => '⟪',
             });
         }
 
@@ -96,6 +170,7 @@ impl<'a> Printer<'a> {
         let group = if fits { Group::Inline } else { Group::Broken };
 
         self.groups_stack.push(group(break_style));
+        self.groups_indents.push(indent);
     }
 
     pub(super) fn end(&mut self) {
@@ -107,10 +182,14 @@ impl<'a> Printer<'a> {
             return;
         };
 
+        self.groups_indents.pop();
+
         if self.config.debug_layout {
             self.output.push(match top_group.break_style() {
-                BreakStyle::Consistent => '»',
+                BreakStyle::Consistent | BreakStyle::ConsistentIfMoreThan(_) => '»',
                 BreakStyle::Compact => '›',
+                BreakStyle::Table => '⟧',
+                BreakStyle::Fill => '⟫',
             });
         }
     }
@@ -120,17 +199,52 @@ impl<'a> Printer<'a> {
             self.output.extend(subscript_number(&diff.to_string()));
         }
 
-        self.indent_level = self
-            .indent_level
+        let indent_level = self
+            .indent
+            .len()
             .checked_add_signed(diff)
             .unwrap_or_else(|| {
                 debug_assert!(
                     false,
-                    "Indent overflow: indent_diff: {diff}, self.indent_level: {}",
-                    self.indent_level
+                    "Indent overflow: indent_diff: {diff}, indent_level: {}",
+                    self.indent.len()
                 );
-                self.indent_level.saturating_add_signed(diff)
+                self.indent.len().saturating_add_signed(diff)
             });
+
+        let indent_str = self
+            .groups_indents
+            .last()
+            .copied()
+            .unwrap_or(self.config.indent_str);
+
+        self.indent.resize(indent_level, indent_str);
+    }
+
+    pub(super) fn align_begin(&mut self) {
+        if self.config.debug_indent {
+            self.output.push('ₐ');
+        }
+
+        self.pending_alignment = true;
+    }
+
+    pub(super) fn align_end(&mut self) {
+        if self.config.debug_indent {
+            self.output.push_str("₋ₐ");
+        }
+
+        // The alignment may have never started if there was no content
+        if std::mem::take(&mut self.pending_alignment) {
+            return;
+        }
+
+        if self.alignments.pop().is_none() {
+            debug_panic!(
+                "Unbalanced alignments stack: trying to end an alignment when
+                there is no alignment started"
+            );
+        }
     }
 
     fn next_token_sequence_fits(&self, size: Size) -> bool {
@@ -148,8 +262,10 @@ impl<'a> Printer<'a> {
             // Even if the group is broken, we still try to fit the tokens on
             // the same line if the break is compact, which is the whole purpose
             // of "consistent/compact" distinction.
-            Group::Broken(BreakStyle::Compact) => size <= self.line_size_budget,
-            Group::Broken(BreakStyle::Consistent) => false,
+            Group::Broken(BreakStyle::Compact | BreakStyle::Fill) => size <= self.line_size_budget,
+            Group::Broken(
+                BreakStyle::Consistent | BreakStyle::ConsistentIfMoreThan(_) | BreakStyle::Table,
+            ) => false,
         }
     }
 
@@ -165,13 +281,38 @@ impl<'a> Printer<'a> {
             self.spaces = Spaces::Skip;
         }
 
+        self.padding = 0;
+
+        let line_indent = self.config.placement.line_indent;
+        let line_indent_size = self.advance_column(0, &line_indent);
+
+        // Aligned lines start with spaces up to the aligned column, and only
+        // the indent levels nested inside of the alignment are added after it
+        let (aligned_column, indent) = match self.alignments.last() {
+            Some(alignment) => (
+                cmp::max(alignment.column, line_indent_size),
+                self.indent
+                    .get(alignment.indent_level..)
+                    .unwrap_or_default(),
+            ),
+            None => (line_indent_size, self.indent.as_slice()),
+        };
+
         self.output.extend(iter::repeat_n('\n', size));
-        self.output.extend(iter::repeat_n(
-            self.config.indent_str.as_str(),
-            self.indent_level,
-        ));
+        self.output.push_str(&line_indent);
+        self.output
+            .extend(iter::repeat_n(' ', aligned_column - line_indent_size));
+        self.output.extend(indent.iter().map(MeasuredStr::as_str));
+
+        let indent_size = indent.iter().fold(aligned_column, |column, level| {
+            if level.contains('\t') {
+                self.advance_column(column, level)
+            } else {
+                column + level.visual_size()
+            }
+        });
 
-        let indent_size = self.indent_level * self.config.indent_str.visual_size();
+        self.column = indent_size;
 
         self.line_size_budget = cmp::max(
             self.config.max_line_size.saturating_sub(indent_size),
@@ -190,29 +331,85 @@ impl<'a> Printer<'a> {
         }
     }
 
-    pub(super) fn soft_break(&mut self, next_space_distance: Size) {
-        if !self.next_token_sequence_fits(next_space_distance) {
-            self.hard_break(1);
+    pub(super) fn padding(&mut self, size: usize, depth: usize) {
+        let group = self
+            .groups_stack
+            .len()
+            .checked_sub(depth + 1)
+            .and_then(|index| self.groups_stack.get(index));
+
+        let Some(Group::Broken(
+            BreakStyle::Consistent | BreakStyle::ConsistentIfMoreThan(_) | BreakStyle::Table,
+        )) = group
+        else {
+            return;
+        };
+
+        if let Spaces::Buffered(_) = self.spaces {
+            self.padding += size;
+            self.decrease_line_size_budget(size);
+        }
+    }
+
+    pub(super) fn soft_break(&mut self, next_space_distance: Size, text: Option<BreakText<'_>>) {
+        if self.next_token_sequence_fits(next_space_distance) {
+            return;
         }
+
+        let Some(text) = text else {
+            self.hard_break(1);
+            return;
+        };
+
+        self.raw(text.before);
+        self.hard_break(1);
+        self.raw(text.after);
     }
 
     pub(super) fn raw(&mut self, str: MeasuredStr<'_>) {
         if let Spaces::Buffered(pending_spaces) = self.spaces {
+            let pending_spaces = pending_spaces + std::mem::take(&mut self.padding);
             self.output.extend(iter::repeat_n(' ', pending_spaces));
+            self.column += pending_spaces;
         }
 
         self.spaces = Spaces::Buffered(0);
 
-        self.decrease_line_size_budget(str.visual_size());
+        if std::mem::take(&mut self.pending_alignment) {
+            self.alignments.push(Alignment {
+                column: self.column,
+                indent_level: self.indent.len(),
+            });
+        }
+
+        let size = if str.contains('\t') {
+            self.advance_column(self.column, &str) - self.column
+        } else {
+            str.visual_size()
+        };
+
+        self.column += size;
+        self.decrease_line_size_budget(size);
+
+        if let Some(source) = str.source() {
+            self.source_map.push(self.output.len(), source, str.len());
+        }
+
         self.output.push_str(&str);
     }
 
+    fn advance_column(&self, column: usize, content: &str) -> usize {
+        self.config
+            .visual_size
+            .advance_column(column, content, self.config.tab_width)
+    }
+
     pub(super) fn line_size_budget(&self) -> usize {
         self.line_size_budget
     }
 
-    pub(super) fn finish(self) -> String {
-        self.output
+    pub(super) fn finish(self) -> (String, SourceMap) {
+        (self.output, self.source_map)
     }
 }
 
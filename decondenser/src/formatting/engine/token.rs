@@ -1,3 +1,4 @@
+use super::BreakText;
 use crate::ansi::{BLACK, BLUE, BOLD, GREEN, NO_BOLD, RESET, WHITE, YELLOW};
 use crate::formatting::BreakStyle;
 use crate::utils::{debug_panic, scope_path};
@@ -12,6 +13,12 @@ pub(super) enum Token<'a> {
         /// the paired [`Token::End`] on the same level of nesting or EOF.
         next_break_distance: Measurement,
         break_style: BreakStyle,
+
+        /// String used to make a single level of indentation of the content
+        indent: MeasuredStr<'a>,
+
+        /// The group is broken regardless of its measured size
+        forced: bool,
     },
 
     /// Raw text that should be printed as-is.
@@ -24,16 +31,39 @@ pub(super) enum Token<'a> {
         /// all tokens until the next [`Token::Space`] on the same level of nesting
         /// or EOF.
         next_break_distance: Measurement,
+
+        /// The break belongs to a group with [`BreakStyle::Fill`], so its
+        /// distance isn't cut short by the breaks inside of the nested groups.
+        /// This way the nested group is moved to the next line as a whole if it
+        /// doesn't fit on the current line.
+        fill: bool,
+
+        /// Text printed around the line break if it takes place
+        text: Option<BreakText<'a>>,
     },
 
     /// A sequence of the given number of whitespace characters. Will be ignored
     /// if it would be printed adjacently to a line break.
     Space(usize),
 
+    /// Additional whitespace appended to the preceding [`Token::Space`], but
+    /// only if the group `depth` levels above the innermost one is broken with
+    /// a consistent or table break style. It doesn't contribute to the
+    /// single-line size of the tokens, because it is never printed when that
+    /// group fits on a single line.
+    Padding { size: usize, depth: usize },
+
     /// Change the indent of the following content by the given number of
     /// levels. Applied only if the group is broken into multiple lines.
     Indent(isize),
 
+    /// Starts the alignment of the following lines to the column where the
+    /// next [`Token::Raw`] is printed. Nested indent is added on top of it.
+    AlignBegin,
+
+    /// Restores the indent that was used before the paired [`Token::AlignBegin`]
+    AlignEnd,
+
     /// Closes a nested group
     End,
 }
@@ -115,20 +145,32 @@ impl fmt::Debug for Token<'_> {
             ),
             Self::SoftBreak {
                 next_break_distance,
+                ..
             } => {
                 write!(f, "{next_break_distance:?}{GREEN}{BOLD}SoftBreak{NO_BOLD}")
             }
             Self::Space(size) => write!(f, "{:?}{WHITE}Space", Size::Fixed(*size)),
+            Self::Padding { size, depth } => {
+                write!(f, "{:?}{WHITE}Padding {depth}", Size::Fixed(*size))
+            }
             Self::Begin {
                 break_style,
                 next_break_distance,
+                forced,
+                ..
             } => {
                 write!(
                     f,
                     "{next_break_distance:?}{YELLOW}{BOLD}Begin{NO_BOLD} {break_style:?}"
-                )
+                )?;
+                if *forced {
+                    write!(f, " (forced)")?;
+                }
+                Ok(())
             }
             Self::Indent(diff) => write!(f, "{BLACK}  - Indent({diff})"),
+            Self::AlignBegin => write!(f, "{BLACK}  - AlignBegin"),
+            Self::AlignEnd => write!(f, "{BLACK}  - AlignEnd"),
             Self::End => write!(f, "{BLACK}  - {BLUE}End"),
         }?;
 
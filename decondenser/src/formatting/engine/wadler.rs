@@ -0,0 +1,935 @@
+use super::printer::{Printer, PrinterConfig};
+use super::token::Size;
+use super::{BreakText, Engine};
+use crate::SourceMap;
+use crate::formatting::BreakStyle;
+use crate::utils::debug_panic;
+use crate::visual_size::{ArcVisualSize, MeasuredStr};
+use std::cell::Cell;
+use std::cmp;
+use std::collections::HashMap;
+use std::ops::ControlFlow;
+
+/// Max number of nested groups that can be kept on a single line while only
+/// the nested group that is their only item is broken. This bounds the
+/// lookahead, which is otherwise exponential to the depth of nesting.
+const MAX_HUG_DEPTH: usize = 3;
+
+/// An alternative to the [`NormalizedFormatter`] in the spirit of the Wadler's
+/// "prettier printer". It buffers the entire document as a tree of groups, and
+/// for every group that doesn't fit on the line it compares the [`Cost`] of
+/// breaking the group with the cost of keeping it on a single line where only
+/// the nested group that is its only item may be broken.
+///
+/// The layout decisions are then fed to the same [`Printer`] that is used by
+/// the [`NormalizedFormatter`], so the output is rendered identically.
+///
+/// The line breaks from the input that don't take place are lost in the output,
+/// so the output would be laid out differently when it's formatted again. To
+/// keep the formatting idempotent, the layout is repeated without such breaks
+/// until all of the remaining ones take place.
+///
+/// [`NormalizedFormatter`]: super::NormalizedFormatter
+pub(super) struct WadlerFormatter<'a> {
+    /// Groups that are not closed yet. The first one is the root of the
+    /// document, that is never closed.
+    stack: Vec<GroupNode<'a>>,
+
+    /// Counter used to generate unique IDs for the groups
+    groups_count: usize,
+
+    layout: Layout,
+    config: PrinterConfig<'a>,
+}
+
+enum Node<'a> {
+    Raw(MeasuredStr<'a>),
+    Space(usize),
+    Padding { size: usize, depth: usize },
+    SoftBreak(Option<BreakText<'a>>),
+    Newline { taken: Cell<bool> },
+    HardBreak(usize),
+    Indent(isize),
+    AlignBegin,
+    AlignEnd,
+    Group(GroupNode<'a>),
+}
+
+struct GroupNode<'a> {
+    id: usize,
+    break_style: BreakStyle,
+    nodes: Vec<Node<'a>>,
+
+    /// Context of every node in `nodes`, at the same indices
+    contexts: Vec<NodeContext>,
+
+    /// String used to make a single level of indentation of the content
+    indent: MeasuredStr<'a>,
+
+    /// Size of the group if it's printed on a single line. It's [`usize::MAX`]
+    /// if the group contains a hard break or it's forced to break.
+    flat_size: usize,
+
+    /// Size of the content of the group up to its first break, including the
+    /// breaks inside of the nested groups. See [`NodeContext`].
+    size_before_break: ControlFlow<usize, usize>,
+
+    /// Whether one of the nested groups is the only item of this group, so it
+    /// may be broken while this group is kept on a single line
+    has_only_item: bool,
+
+    /// The group is broken regardless of whether it fits on the line
+    forced: bool,
+}
+
+/// Sizes of the content that follows the node inside of its group. They are
+/// computed once when the group ends, so the layout doesn't need to rescan the
+/// rest of the group for every node.
+///
+/// The sizes up to the next break are [`ControlFlow::Break`] if the break is
+/// found, and [`ControlFlow::Continue`] with the size of all the following
+/// nodes otherwise.
+#[derive(Clone, Copy)]
+struct NodeContext {
+    /// Size of all the nodes after this one if they are printed on a single line
+    flat_size_after: usize,
+
+    /// Size of the nodes up to the next break on the same level of nesting
+    size_until_break: ControlFlow<usize, usize>,
+
+    /// Same as `size_until_break`, but also stops at the first break inside of
+    /// the nested groups
+    size_until_nested_break: ControlFlow<usize, usize>,
+
+    /// Whether there are no breaks between the node and the delimiters of the
+    /// group. The opening delimiter is followed by the positive indent (or the
+    /// start of the alignment) and its own break, and the closing delimiter is
+    /// preceded by the negative indent (or the end of the alignment).
+    is_only_item: bool,
+}
+
+impl NodeContext {
+    /// Size of the nodes up to the next break on the same level of nesting. If
+    /// there is no break, then the `trailing` size that follows the nodes is
+    /// added.
+    fn size_until_break(&self, trailing: usize) -> usize {
+        match self.size_until_break {
+            ControlFlow::Break(size) => size,
+            ControlFlow::Continue(size) => size.saturating_add(trailing),
+        }
+    }
+
+    /// Same as [`NodeContext::size_until_break`], but also stops at the first
+    /// break inside of the nested groups.
+    fn size_until_nested_break(&self, trailing: usize) -> usize {
+        match self.size_until_nested_break {
+            ControlFlow::Break(size) => size,
+            ControlFlow::Continue(size) => size.saturating_add(trailing),
+        }
+    }
+}
+
+impl<'a> GroupNode<'a> {
+    fn new(id: usize, break_style: BreakStyle, indent: MeasuredStr<'a>) -> Self {
+        Self {
+            id,
+            break_style,
+            nodes: vec![],
+            contexts: vec![],
+            indent,
+            flat_size: 0,
+            size_before_break: ControlFlow::Continue(0),
+            has_only_item: false,
+            forced: false,
+        }
+    }
+
+    /// Computes the sizes of the content of the group once all of its nodes
+    /// are known. The sizes that follow the nodes are accumulated backwards,
+    /// and the breaks that precede them are tracked forwards.
+    fn finish(&mut self) {
+        let is_break = |node: &Node<'_>| {
+            matches!(
+                node,
+                Node::SoftBreak(_) | Node::Newline { .. } | Node::HardBreak(_)
+            )
+        };
+        let is_opening = |node: &Node<'_>| matches!(node, Node::AlignBegin | Node::Indent(1..));
+        let is_closing = |node: &Node<'_>| matches!(node, Node::AlignEnd | Node::Indent(..0));
+
+        let mut flat_size: usize = 0;
+        let mut until_break = ControlFlow::Continue(0);
+        let mut until_nested_break = ControlFlow::Continue(0);
+
+        // Whether there is a break between the node and the closing delimiter
+        let mut break_after = false;
+
+        let mut contexts = Vec::with_capacity(self.nodes.len());
+
+        for node in self.nodes.iter().rev() {
+            contexts.push(NodeContext {
+                flat_size_after: flat_size,
+                size_until_break: until_break,
+                size_until_nested_break: until_nested_break,
+                is_only_item: !break_after,
+            });
+
+            let size = node.flat_size();
+            flat_size = flat_size.saturating_add(size);
+
+            if is_break(node) {
+                until_break = ControlFlow::Break(line_end_size(node));
+                until_nested_break = ControlFlow::Break(line_end_size(node));
+                break_after = true;
+                continue;
+            }
+
+            if is_closing(node) {
+                break_after = false;
+            }
+
+            let add = |after: ControlFlow<usize, usize>, size: usize| match after {
+                ControlFlow::Break(after) => ControlFlow::Break(size.saturating_add(after)),
+                ControlFlow::Continue(after) => ControlFlow::Continue(size.saturating_add(after)),
+            };
+
+            until_break = add(until_break, size);
+
+            until_nested_break = match node {
+                Node::Group(group) => match group.size_before_break {
+                    ControlFlow::Break(nested) => ControlFlow::Break(nested),
+                    ControlFlow::Continue(nested) => add(until_nested_break, nested),
+                },
+                _ => add(until_nested_break, size),
+            };
+        }
+
+        contexts.reverse();
+
+        // The breaks that immediately follow the opening delimiter are its own
+        let mut after_opening = true;
+        let mut break_before = false;
+
+        for (node, context) in self.nodes.iter().zip(&mut contexts) {
+            context.is_only_item &= !break_before;
+
+            if is_opening(node) {
+                after_opening = true;
+                break_before = false;
+            } else if !is_break(node) {
+                after_opening = false;
+            } else if !after_opening {
+                break_before = true;
+            }
+        }
+
+        self.has_only_item = self
+            .nodes
+            .iter()
+            .zip(&contexts)
+            .any(|(node, context)| matches!(node, Node::Group(_)) && context.is_only_item);
+
+        self.flat_size = if self.forced { usize::MAX } else { flat_size };
+        self.size_before_break = until_nested_break;
+        self.contexts = contexts;
+    }
+
+    /// Removes the line breaks from the input that didn't take place when the
+    /// layout was printed. Returns whether any of them were removed.
+    fn drop_newlines(&mut self) -> bool {
+        let mut dropped = false;
+
+        self.nodes.retain_mut(|node| match node {
+            Node::Newline { taken } => {
+                dropped |= !taken.get();
+                taken.get()
+            }
+            Node::Group(group) => {
+                dropped |= group.drop_newlines();
+                true
+            }
+            _ => true,
+        });
+
+        // The sizes of the nested groups could change too
+        if dropped {
+            self.finish();
+        }
+
+        dropped
+    }
+}
+
+impl Node<'_> {
+    fn flat_size(&self) -> usize {
+        match self {
+            Self::Raw(content) => content.visual_size(),
+            Self::Space(size) => *size,
+            Self::Padding { .. }
+            | Self::SoftBreak(_)
+            | Self::Newline { .. }
+            | Self::Indent(_)
+            | Self::AlignBegin
+            | Self::AlignEnd => 0,
+            Self::HardBreak(_) => usize::MAX,
+            Self::Group(group) => group.flat_size,
+        }
+    }
+}
+
+/// Size of the text that ends the line if the break takes place
+fn line_end_size(node: &Node<'_>) -> usize {
+    match node {
+        Node::SoftBreak(Some(text)) => text.before.visual_size(),
+        _ => 0,
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    /// The group is printed on a single line, its soft breaks are ignored
+    Flat,
+    Broken(BreakStyle),
+}
+
+/// Simulated state of the printer used to measure the cost of the layout.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+struct Position {
+    column: usize,
+
+    /// The column after which the current line overflows
+    limit: usize,
+
+    /// Size of the indentation of the lines outside of the alignment
+    indent: usize,
+
+    /// The column and the size of the indentation at the start of the
+    /// innermost alignment
+    alignment: Option<(usize, usize)>,
+
+    /// Whether the alignment starts at the column of the next raw content
+    pending_alignment: bool,
+
+    /// Pending spaces or `None` if spaces are skipped at the start of the line
+    spaces: Option<usize>,
+}
+
+impl Position {
+    /// Checks if the content of the given size fits on the current line. The
+    /// size may be [`usize::MAX`] if the content contains a forced break.
+    fn fits(&self, size: usize) -> bool {
+        self.overflow(size) == 0
+    }
+
+    /// Size of the part of the content that doesn't fit on the current line
+    fn overflow(&self, size: usize) -> usize {
+        (self.column + self.spaces.unwrap_or(0))
+            .saturating_add(size)
+            .saturating_sub(self.limit)
+    }
+}
+
+/// The cost of the layout. The overflow is compared first, so that the layout
+/// with fewer lines never wins if it overflows more.
+#[derive(Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+struct Cost {
+    overflow: usize,
+    lines: usize,
+}
+
+impl Cost {
+    fn add(self, other: Self) -> Self {
+        Self {
+            overflow: self.overflow.saturating_add(other.overflow),
+            lines: self.lines.saturating_add(other.lines),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+struct Key {
+    group: usize,
+    start: Position,
+    trailing: usize,
+    hug_depth: usize,
+}
+
+#[derive(Clone, Copy)]
+struct Outcome {
+    broken: bool,
+    end: Position,
+    cost: Cost,
+}
+
+impl Outcome {
+    /// Cost of the outcome including the overflow of its last line
+    fn total_cost(&self, trailing: usize) -> Cost {
+        let end = self.end;
+        let column = end.column.saturating_add(trailing);
+
+        self.cost.add(Cost {
+            overflow: column.saturating_sub(end.limit),
+            lines: 0,
+        })
+    }
+}
+
+struct Layout {
+    max_line_size: usize,
+    no_break_size: usize,
+
+    /// Column where the first line of the output starts
+    start_column: usize,
+
+    /// Size of the prefix of every line of the output after the first one
+    line_indent_size: usize,
+
+    /// Used to measure the indent levels with tabs, which depends on the
+    /// column where they are printed
+    visual_size: ArcVisualSize,
+    tab_width: usize,
+
+    /// Memoized layout decisions for the groups that don't fit on the line
+    outcomes: HashMap<Key, Outcome>,
+}
+
+/// Walks over the document tree, and optionally prints it
+struct Cursor<'p, 'a> {
+    position: Position,
+    cost: Cost,
+    printer: Option<&'p mut Printer<'a>>,
+}
+
+impl<'a> WadlerFormatter<'a> {
+    pub(super) fn new(config: PrinterConfig<'a>) -> Self {
+        let root = GroupNode::new(0, BreakStyle::Compact, config.indent_str);
+
+        Self {
+            stack: vec![root],
+            groups_count: 1,
+            layout: Layout {
+                max_line_size: config.max_line_size,
+                no_break_size: config.no_break_size,
+                start_column: config.placement.start_column,
+                line_indent_size: config.visual_size.advance_column(
+                    0,
+                    &config.placement.line_indent,
+                    config.tab_width,
+                ),
+                visual_size: config.visual_size.clone(),
+                tab_width: config.tab_width,
+                outcomes: HashMap::new(),
+            },
+            config,
+        }
+    }
+
+    fn push(&mut self, node: Node<'a>) {
+        if let Some(group) = self.stack.last_mut() {
+            group.nodes.push(node);
+        }
+    }
+}
+
+impl<'a> Engine<'a> for WadlerFormatter<'a> {
+    fn begin(&mut self, break_style: BreakStyle, indent: MeasuredStr<'a>) {
+        self.stack
+            .push(GroupNode::new(self.groups_count, break_style, indent));
+        self.groups_count += 1;
+    }
+
+    fn end(&mut self) {
+        if self.stack.len() <= 1 {
+            debug_panic!(
+                "Unbalanced groups stack: trying to end a group when
+                there is no group started"
+            );
+            return;
+        }
+
+        let Some(mut group) = self.stack.pop() else {
+            return;
+        };
+
+        group.finish();
+
+        self.push(Node::Group(group));
+    }
+
+    fn force_break(&mut self) {
+        for group in &mut self.stack {
+            group.forced = true;
+        }
+    }
+
+    fn indent(&mut self, diff: isize) {
+        self.push(Node::Indent(diff));
+    }
+
+    fn align_begin(&mut self) {
+        self.push(Node::AlignBegin);
+    }
+
+    fn align_end(&mut self) {
+        self.push(Node::AlignEnd);
+    }
+
+    fn hard_break(&mut self, size: usize) {
+        self.push(Node::HardBreak(size));
+    }
+
+    fn soft_break(&mut self) {
+        self.push(Node::SoftBreak(None));
+    }
+
+    fn newline(&mut self) {
+        self.push(Node::Newline {
+            taken: Cell::new(false),
+        });
+    }
+
+    fn split(&mut self, text: BreakText<'a>) {
+        self.push(Node::SoftBreak(Some(text)));
+    }
+
+    fn space(&mut self, size: usize) {
+        self.push(Node::Space(size));
+    }
+
+    fn padding(&mut self, size: usize, depth: usize) {
+        self.push(Node::Padding { size, depth });
+    }
+
+    fn raw(&mut self, content: MeasuredStr<'a>) {
+        self.push(Node::Raw(content));
+    }
+
+    fn eoi(mut self: Box<Self>) -> (String, SourceMap) {
+        while self.stack.len() > 1 {
+            debug_panic!("Unclosed group at the end of input");
+            self.end();
+        }
+
+        let Some(mut root) = self.stack.pop() else {
+            return Printer::new(self.config).finish();
+        };
+
+        root.finish();
+
+        loop {
+            let mut printer = Printer::new(self.config);
+
+            let mut cursor = Cursor {
+                position: self.layout.start(),
+                cost: Cost::default(),
+                printer: Some(&mut printer),
+            };
+
+            let mode = Mode::Broken(root.break_style);
+            self.layout.nodes(&root, mode, 0, 0, &mut cursor);
+
+            if !root.drop_newlines() {
+                return printer.finish();
+            }
+
+            // The decisions depend on the removed breaks
+            self.layout.outcomes.clear();
+        }
+    }
+}
+
+impl Layout {
+    fn start(&self) -> Position {
+        Position {
+            column: self.start_column,
+            limit: cmp::max(self.max_line_size, self.start_column + self.no_break_size),
+            indent: 0,
+            alignment: None,
+            pending_alignment: false,
+            spaces: None,
+        }
+    }
+
+    /// Lays out the nodes inside of the group in the given mode.
+    ///
+    /// - `trailing` is the size of the content that follows the nodes up to the
+    ///   next break in the parent group.
+    /// - `hug_depth` is the number of nested groups that can still be kept on a
+    ///   single line while their only item is broken.
+    fn nodes<'a>(
+        &mut self,
+        group: &GroupNode<'a>,
+        mode: Mode,
+        trailing: usize,
+        hug_depth: usize,
+        cursor: &mut Cursor<'_, 'a>,
+    ) {
+        let nodes = &group.nodes;
+
+        // Alignments that were replaced by the nested ones in this group
+        let mut alignments = vec![];
+
+        // Indents that were replaced by the nested ones in this group
+        let mut indents = vec![];
+
+        for (node, context) in nodes.iter().zip(&group.contexts) {
+            match node {
+                Node::Raw(content) => cursor.raw(*content),
+                Node::Space(size) => cursor.space(*size),
+                Node::Padding { size, depth } => {
+                    if let Some(printer) = &mut cursor.printer {
+                        printer.padding(*size, *depth);
+                    }
+                }
+                Node::Indent(diff) => {
+                    // Indent is irrelevant if the group is on a single line,
+                    // but it must not leak into the nested broken group.
+                    if mode != Mode::Flat {
+                        let indent = match diff {
+                            1.. => {
+                                indents.push(cursor.position.indent);
+                                self.indented(cursor.position, *diff, group.indent)
+                            }
+                            _ => indents.pop().unwrap_or_else(|| {
+                                let size = diff.unsigned_abs() * group.indent.visual_size();
+                                cursor.position.indent.saturating_sub(size)
+                            }),
+                        };
+                        cursor.indent(*diff, indent);
+                    }
+                }
+                Node::AlignBegin => {
+                    if mode != Mode::Flat {
+                        alignments.push(cursor.position.alignment);
+                        cursor.align_begin();
+                    }
+                }
+                Node::AlignEnd => {
+                    if let Some(alignment) = alignments.pop() {
+                        cursor.align_end(alignment);
+                    }
+                }
+                Node::HardBreak(size) => self.hard_break(*size, cursor),
+                Node::SoftBreak(text) => {
+                    let needs_break = needs_break(mode, context, trailing, cursor.position);
+                    self.soft_break(needs_break, *text, cursor);
+                }
+                Node::Newline { taken } => {
+                    let needs_break = needs_break(mode, context, trailing, cursor.position);
+
+                    if cursor.printer.is_some() {
+                        taken.set(needs_break);
+                    }
+
+                    self.soft_break(needs_break, None, cursor);
+                }
+                Node::Group(group) => match mode {
+                    Mode::Flat => {
+                        let trailing = context.flat_size_after.saturating_add(trailing);
+
+                        // Only the nested group that is the only item of the
+                        // group on a single line may be broken, otherwise the
+                        // content around it would look misplaced.
+                        let hug_depth = if context.is_only_item { hug_depth } else { 0 };
+
+                        self.group(group, trailing, hug_depth, cursor);
+                    }
+                    Mode::Broken(_) => {
+                        let trailing = context.size_until_break(trailing);
+                        self.group(group, trailing, MAX_HUG_DEPTH + 1, cursor);
+                    }
+                },
+            }
+        }
+    }
+
+    /// The `hug_depth` of zero means that the group can't be broken unless it's
+    /// forced to. Otherwise `hug_depth - 1` is passed to the nested groups if
+    /// this group is kept on a single line.
+    fn group<'a>(
+        &mut self,
+        group: &GroupNode<'a>,
+        trailing: usize,
+        hug_depth: usize,
+        cursor: &mut Cursor<'_, 'a>,
+    ) {
+        let outcome = if hug_depth == 0 && !group.forced {
+            None
+        } else {
+            Some(self.decide(
+                group,
+                cursor.position,
+                trailing,
+                hug_depth.saturating_sub(1),
+            ))
+        };
+
+        let broken = outcome.is_some_and(|outcome| outcome.broken);
+
+        let Some(printer) = &mut cursor.printer else {
+            if let Some(outcome) = outcome {
+                cursor.position = outcome.end;
+                cursor.cost = cursor.cost.add(outcome.cost);
+            } else {
+                self.nodes(group, Mode::Flat, trailing, 0, cursor);
+            }
+            return;
+        };
+
+        let distance = if broken {
+            Size::Infinite
+        } else {
+            Size::Fixed(0)
+        };
+        printer.begin(group.break_style, group.indent, distance);
+
+        let (mode, hug_depth) = if broken {
+            (Mode::Broken(group.break_style), 0)
+        } else {
+            (Mode::Flat, hug_depth.saturating_sub(1))
+        };
+
+        self.nodes(group, mode, trailing, hug_depth, cursor);
+
+        if let Some(printer) = &mut cursor.printer {
+            printer.end();
+        }
+    }
+
+    /// Decides whether the group should be broken by comparing the costs of
+    /// the alternative layouts.
+    fn decide(
+        &mut self,
+        group: &GroupNode<'_>,
+        start: Position,
+        trailing: usize,
+        hug_depth: usize,
+    ) -> Outcome {
+        let key = Key {
+            group: group.id,
+            start,
+            trailing,
+            hug_depth,
+        };
+
+        if let Some(&outcome) = self.outcomes.get(&key) {
+            return outcome;
+        }
+
+        let size = group.flat_size.saturating_add(trailing);
+        let fits = start.fits(size);
+
+        let outcome = if group.forced {
+            self.simulate(group, Mode::Broken(group.break_style), start, trailing, 0)
+        } else if fits {
+            self.simulate(group, Mode::Flat, start, trailing, hug_depth)
+        } else if hug_depth == 0 || !group.has_only_item {
+            // Nothing inside of the group can be broken if it's on a single
+            // line, so the overflow of such layout is measured by its flat size
+            // without simulating it. The group is broken like in the classic
+            // layout, unless breaking makes the overflow worse.
+            let broken = self.simulate(group, Mode::Broken(group.break_style), start, trailing, 0);
+
+            if start.overflow(size) < broken.total_cost(trailing).overflow {
+                self.simulate(group, Mode::Flat, start, trailing, 0)
+            } else {
+                broken
+            }
+        } else {
+            let flat = self.simulate(group, Mode::Flat, start, trailing, hug_depth);
+            let broken = self.simulate(group, Mode::Broken(group.break_style), start, trailing, 0);
+
+            let flat_cost = flat.total_cost(trailing);
+            let broken_cost = broken.total_cost(trailing);
+
+            // The group that overflows while it's entirely on a single line
+            // is treated the same as above. If the nested group is broken
+            // instead, then the layouts compete by the cost as usual.
+            let keep_flat = if flat_cost.overflow > 0 && flat_cost.lines == 0 {
+                flat_cost.overflow < broken_cost.overflow
+            } else {
+                flat_cost < broken_cost
+            };
+
+            if keep_flat { flat } else { broken }
+        };
+
+        self.outcomes.insert(key, outcome);
+
+        outcome
+    }
+
+    fn simulate(
+        &mut self,
+        group: &GroupNode<'_>,
+        mode: Mode,
+        start: Position,
+        trailing: usize,
+        hug_depth: usize,
+    ) -> Outcome {
+        let mut cursor = Cursor {
+            position: start,
+            cost: Cost::default(),
+            printer: None,
+        };
+
+        self.nodes(group, mode, trailing, hug_depth, &mut cursor);
+
+        Outcome {
+            broken: matches!(mode, Mode::Broken(_)),
+            end: cursor.position,
+            cost: cursor.cost,
+        }
+    }
+
+    fn soft_break(
+        &self,
+        needs_break: bool,
+        text: Option<BreakText<'_>>,
+        cursor: &mut Cursor<'_, '_>,
+    ) {
+        if needs_break {
+            if let Some(text) = text {
+                cursor.advance(text.before);
+            }
+
+            self.new_lines(1, cursor);
+
+            if let Some(text) = text {
+                cursor.advance(text.after);
+            }
+        }
+
+        if let Some(printer) = &mut cursor.printer {
+            // The decision was already made, so force the printer to obey it
+            let distance = if needs_break {
+                Size::Infinite
+            } else {
+                Size::Fixed(0)
+            };
+            printer.soft_break(distance, text);
+        }
+    }
+
+    fn hard_break(&self, size: usize, cursor: &mut Cursor<'_, '_>) {
+        self.new_lines(size, cursor);
+
+        if let Some(printer) = &mut cursor.printer {
+            printer.hard_break(size);
+        }
+    }
+
+    /// Column where the content of the new line starts after the indent
+    fn line_start(&self, position: &Position) -> usize {
+        match position.alignment {
+            Some((column, indent)) => column + position.indent.saturating_sub(indent),
+            None => self.line_indent_size + position.indent,
+        }
+    }
+
+    /// Size of the indentation after adding `diff` levels of `indent_str` to
+    /// it. The levels are measured at the column where they are printed, so
+    /// the tabs in them advance to the tab stops the same way as in `Printer`.
+    fn indented(&self, position: Position, diff: isize, indent_str: MeasuredStr<'_>) -> usize {
+        let start = self.line_start(&position);
+
+        let end = (0..diff).fold(start, |column, _| {
+            if indent_str.contains('\t') {
+                self.visual_size
+                    .advance_column(column, &indent_str, self.tab_width)
+            } else {
+                column + indent_str.visual_size()
+            }
+        });
+
+        position.indent + (end - start)
+    }
+
+    fn new_lines(&self, size: usize, cursor: &mut Cursor<'_, '_>) {
+        let position = &mut cursor.position;
+
+        cursor.cost = cursor.cost.add(Cost {
+            overflow: position.column.saturating_sub(position.limit),
+            lines: size,
+        });
+
+        position.column = self.line_start(position);
+        position.limit = cmp::max(self.max_line_size, position.column + self.no_break_size);
+        position.spaces = None;
+    }
+}
+
+/// Whether the soft break in the group in the given mode takes place
+fn needs_break(mode: Mode, context: &NodeContext, trailing: usize, position: Position) -> bool {
+    match mode {
+        Mode::Flat => false,
+        Mode::Broken(BreakStyle::Compact) => {
+            !position.fits(context.size_until_nested_break(trailing))
+        }
+        Mode::Broken(BreakStyle::Fill) => !position.fits(context.size_until_break(trailing)),
+        Mode::Broken(
+            BreakStyle::Consistent | BreakStyle::ConsistentIfMoreThan(_) | BreakStyle::Table,
+        ) => true,
+    }
+}
+
+impl<'a> Cursor<'_, 'a> {
+    fn raw(&mut self, content: MeasuredStr<'a>) {
+        self.advance(content);
+
+        if let Some(printer) = &mut self.printer {
+            printer.raw(content);
+        }
+    }
+
+    /// Moves the position past the content without printing it
+    fn advance(&mut self, content: MeasuredStr<'_>) {
+        let position = &mut self.position;
+        position.column += position.spaces.unwrap_or(0);
+
+        if std::mem::take(&mut position.pending_alignment) {
+            position.alignment = Some((position.column, position.indent));
+        }
+
+        position.column += content.visual_size();
+        position.spaces = Some(0);
+    }
+
+    fn space(&mut self, size: usize) {
+        if self.position.spaces.is_some() {
+            self.position.spaces = Some(size);
+        }
+
+        if let Some(printer) = &mut self.printer {
+            printer.space(size);
+        }
+    }
+
+    /// Changes the indent level by `diff`, where `indent` is the resulting
+    /// size of the indentation
+    fn indent(&mut self, diff: isize, indent: usize) {
+        self.position.indent = indent;
+
+        if let Some(printer) = &mut self.printer {
+            printer.indent(diff);
+        }
+    }
+
+    fn align_begin(&mut self) {
+        self.position.pending_alignment = true;
+
+        if let Some(printer) = &mut self.printer {
+            printer.align_begin();
+        }
+    }
+
+    /// Restores the alignment that was used before the paired `align_begin`
+    fn align_end(&mut self, alignment: Option<(usize, usize)>) {
+        self.position.pending_alignment = false;
+        self.position.alignment = alignment;
+
+        if let Some(printer) = &mut self.printer {
+            printer.align_end();
+        }
+    }
+}
@@ -0,0 +1,179 @@
+use crate::parsing::l2::{self, TokenTree};
+use crate::{Decondenser, FormatError, config};
+use std::borrow::Cow;
+
+/// Non-whitespace token of the input. The whitespace is rewritten by the
+/// formatter freely, but everything else must be preserved as is.
+#[derive(Debug, PartialEq, Eq)]
+enum Content<'a> {
+    Raw(Cow<'a, str>),
+    Punct(&'a str),
+    Quoted(Quoted<'a>),
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct Quoted<'a> {
+    config: QuoteConfig<'a>,
+    content: Cow<'a, str>,
+    closed: bool,
+}
+
+/// Compared by the address, because several quotes may share the delimiters
+#[derive(Debug, Clone, Copy)]
+struct QuoteConfig<'a>(&'a config::Quote);
+
+impl PartialEq for QuoteConfig<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self.0, other.0)
+    }
+}
+
+impl Eq for QuoteConfig<'_> {}
+
+impl Decondenser {
+    /// Checks that the output has the same non-whitespace tokens as the input.
+    pub(crate) fn check_equivalence(&self, input: &str, output: &str) -> Result<(), FormatError> {
+        let input_tokens = l2::parse(self, input);
+        let output_tokens = l2::parse(self, output);
+
+        let input = self.content(&input_tokens);
+        let output = self.content(&output_tokens);
+
+        let len = std::cmp::max(input.len(), output.len());
+
+        let Some(index) = (0..len).find(|&index| input.get(index) != output.get(index)) else {
+            return Ok(());
+        };
+
+        let describe = |content: Option<&Content<'_>>| match content {
+            Some(content) => format!("{content:?}"),
+            None => "{end of text}".to_owned(),
+        };
+
+        Err(FormatError::ContentChanged {
+            input: describe(input.get(index)),
+            output: describe(output.get(index)),
+        })
+    }
+
+    fn content<'a>(&self, tokens: &'a [TokenTree<'a>]) -> Vec<Content<'a>> {
+        let mut content = vec![];
+        flatten(tokens, &mut content);
+
+        // The content split by the formatter via `wrap_raw_after` and quotes'
+        // `concat_operator` is joined back on both sides of the comparison
+        let mut joined = vec![];
+
+        for next in content {
+            self.join(&mut joined, next);
+        }
+
+        joined
+    }
+
+    fn join<'a>(&self, joined: &mut Vec<Content<'a>>, next: Content<'a>) {
+        match next {
+            Content::Raw(next) => {
+                if let Some(Content::Raw(prev)) = joined.last_mut() {
+                    if self.wraps_after(prev) {
+                        prev.to_mut().push_str(&next);
+                        return;
+                    }
+                }
+                joined.push(Content::Raw(next));
+            }
+            Content::Quoted(next) => {
+                if let Some(prev) = concat_target(joined, next.config) {
+                    prev.content.to_mut().push_str(&next.content);
+                    prev.closed = next.closed;
+                    return;
+                }
+                joined.push(Content::Quoted(next));
+            }
+            next @ Content::Punct(_) => joined.push(next),
+        }
+    }
+
+    pub(super) fn wraps_after(&self, raw: &str) -> bool {
+        self.wrap_raw_after
+            .iter()
+            .any(|sequence| !sequence.is_empty() && raw.ends_with(&**sequence))
+    }
+}
+
+/// Returns the preceding quoted content if the next quoted content with the
+/// given config may be its continuation split with the concat operator.
+fn concat_target<'j, 'a>(
+    joined: &'j mut Vec<Content<'a>>,
+    config: QuoteConfig<'a>,
+) -> Option<&'j mut Quoted<'a>> {
+    let operator = concat_operator(config.0)?;
+
+    if !operator.is_empty() {
+        match joined.last()? {
+            Content::Raw(token) if token == operator => {}
+            Content::Punct(token) if *token == operator => {}
+            _ => return None,
+        }
+    }
+
+    let prev_index = joined
+        .len()
+        .checked_sub(usize::from(!operator.is_empty()) + 1)?;
+
+    let Content::Quoted(prev) = joined.get(prev_index)? else {
+        return None;
+    };
+
+    if !prev.closed || prev.config != config {
+        return None;
+    }
+
+    joined.truncate(prev_index + 1);
+
+    match joined.last_mut()? {
+        Content::Quoted(prev) => Some(prev),
+        _ => None,
+    }
+}
+
+/// The operator configured via [`Quote::concat_operator()`].
+///
+/// [`Quote::concat_operator()`]: crate::Quote::concat_operator()
+pub(super) fn concat_operator(config: &config::Quote) -> Option<&str> {
+    let split_closing = config.split_closing.as_deref()?;
+    let operator = split_closing.strip_prefix(&*config.closing)?;
+    Some(operator.trim_start())
+}
+
+fn flatten<'a>(tokens: &'a [TokenTree<'a>], content: &mut Vec<Content<'a>>) {
+    for token in tokens {
+        match token {
+            TokenTree::Space(_) | TokenTree::Newline(_) => {}
+            TokenTree::Raw { content: raw, .. } | TokenTree::AnsiEscape(raw) => {
+                content.push(Content::Raw(Cow::Borrowed(raw)));
+            }
+            TokenTree::Punct(punct) => content.push(Content::Punct(&punct.config.symbol)),
+            TokenTree::Group(group) => {
+                content.push(Content::Punct(&group.config.opening.symbol));
+                flatten(&group.content, content);
+
+                if group.closing.is_some() {
+                    content.push(Content::Punct(&group.config.closing.symbol));
+                }
+            }
+            TokenTree::Quoted(quoted) => {
+                let quoted_content = match quoted.content.as_slice() {
+                    [single] => Cow::Borrowed(single.source()),
+                    pieces => Cow::Owned(pieces.iter().map(l2::QuotedContent::source).collect()),
+                };
+
+                content.push(Content::Quoted(Quoted {
+                    config: QuoteConfig(quoted.config),
+                    content: quoted_content,
+                    closed: quoted.closing.is_some(),
+                }));
+            }
+        }
+    }
+}
@@ -0,0 +1,81 @@
+use crate::parsing::l2::TokenTree;
+use crate::{Decondenser, Punct};
+
+/// Approximates the single-line size of the tokens as they would be formatted
+/// if they fit on a single line.
+pub(super) struct Measure<'c> {
+    config: &'c Decondenser,
+}
+
+impl<'c> Measure<'c> {
+    pub(super) fn new(config: &'c Decondenser) -> Self {
+        Self { config }
+    }
+
+    fn str(&self, str: &str) -> usize {
+        self.config.visual_size.measure(str, self.config.tab_width)
+    }
+
+    fn punct(&self, punct: &Punct) -> usize {
+        punct.leading_space.size.0 + self.str(&punct.symbol) + punct.trailing_space.size.0
+    }
+
+    pub(super) fn token(&self, token: &TokenTree<'_>) -> usize {
+        match token {
+            TokenTree::Space(_) | TokenTree::Newline(_) => 1,
+            TokenTree::Raw { content, .. } => self.str(content),
+            TokenTree::AnsiEscape(_) => 0,
+            TokenTree::Punct(punct) => self.punct(punct.config),
+            TokenTree::Group(group) => {
+                let content = group
+                    .content
+                    .iter()
+                    .map(|token| self.token(token))
+                    .sum::<usize>();
+
+                let closing = if group.closing.is_some() {
+                    self.punct(&group.config.closing)
+                } else {
+                    0
+                };
+
+                self.punct(&group.config.opening) + content + closing
+            }
+            TokenTree::Quoted(quoted) => {
+                let content = quoted
+                    .content
+                    .iter()
+                    .map(|content| self.str(content.source()))
+                    .sum::<usize>();
+
+                let closing = if quoted.closing.is_some() {
+                    self.str(&quoted.config.closing)
+                } else {
+                    0
+                };
+
+                self.str(&quoted.config.opening) + content + closing
+            }
+        }
+    }
+
+    /// Size of the sequence of tokens with leading and trailing blanks trimmed.
+    pub(super) fn tokens(&self, tokens: &[TokenTree<'_>]) -> usize {
+        trim_blanks(tokens)
+            .iter()
+            .map(|token| self.token(token))
+            .sum()
+    }
+}
+
+pub(super) fn trim_blanks<'a, 'i>(mut tokens: &'a [TokenTree<'i>]) -> &'a [TokenTree<'i>] {
+    while let [TokenTree::Space(_) | TokenTree::Newline(_), rest @ ..] = tokens {
+        tokens = rest;
+    }
+
+    while let [rest @ .., TokenTree::Space(_) | TokenTree::Newline(_)] = tokens {
+        tokens = rest;
+    }
+
+    tokens
+}
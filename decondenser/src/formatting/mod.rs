@@ -1,19 +1,51 @@
+mod align;
 mod engine;
-
-use self::engine::{Formatter, MeasuredStr};
+mod equivalence;
+mod measure;
+mod range;
+mod strict;
+mod table;
+
+use self::align::Alignment;
+use self::engine::{Formatter, MeasuredStr, Placement};
+use self::equivalence::concat_operator;
+use self::measure::trim_blanks;
+use self::table::{Columns, Table};
 use crate::config::BreakStyleEnum as BreakStyle;
-use crate::parsing;
-use crate::parsing::l2::TokenTree;
+use crate::config::IndentStyleEnum as IndentStyle;
+use crate::parsing::l2::{QuotedContent, TokenTree};
+use crate::parsing::quoted::l1::Unescaped;
+use crate::utils::debug_panic;
+use crate::{SourceMap, parsing};
 
 impl crate::Decondenser {
     /// This function lives here to keep the `lib.rs` file lean and focused on
     /// the public API of the `Decondenser` struct.
-    pub(crate) fn format_impl(&self, input: &str) -> String {
+    pub(crate) fn format_impl(&self, input: &str) -> (String, SourceMap) {
+        self.format_at(input, 0, "")
+    }
+
+    /// Formats the input as if it was placed at the `start_column` of the line
+    /// that begins with the `line_indent`. The `line_indent` is repeated at the
+    /// start of every line of the output after the first one.
+    pub(crate) fn format_at(
+        &self,
+        input: &str,
+        start_column: usize,
+        line_indent: &str,
+    ) -> (String, SourceMap) {
         let tokens = parsing::l2::parse(self, input);
 
-        let mut fmt = Formatter::new(self);
+        let placement = Placement {
+            start_column,
+            line_indent: self.visual_size.measured_str(line_indent, self.tab_width),
+        };
+
+        let mut fmt = Formatter::new(self, placement);
+
+        let indent = self.visual_size.measured_str(&self.indent, self.tab_width);
 
-        fmt.begin(BreakStyle::Consistent);
+        fmt.begin(BreakStyle::Consistent, indent);
 
         FormattingCtx {
             config: self,
@@ -21,11 +53,25 @@ impl crate::Decondenser {
             tokens: TokensCursor {
                 tokens: tokens.iter(),
             },
+            alignment: None,
+            table: None,
+            columns: None,
         }
         .format();
 
         fmt.end();
-        fmt.eoi()
+
+        let (output, source_map) = fmt.eoi();
+
+        // The check is too expensive to run it for every format in release
+        // builds, but this way all tests verify that the content is intact
+        if cfg!(debug_assertions) && !self.debug_layout && !self.debug_indent {
+            if let Err(err) = self.check_equivalence(input, &output) {
+                debug_panic!("{err}");
+            }
+        }
+
+        (output, source_map)
     }
 }
 
@@ -33,39 +79,247 @@ struct FormattingCtx<'f, 'i> {
     config: &'i crate::Decondenser,
     fmt: &'f mut Formatter<'i>,
     tokens: TokensCursor<'i>,
+    alignment: Option<Alignment<'i>>,
+
+    /// Layout of the rows if this is the content of a table group
+    table: Option<Table<'i>>,
+
+    /// Paddings of the fields if this is the content of a table row
+    columns: Option<Columns>,
 }
 
 impl<'i> FormattingCtx<'_, 'i> {
     pub(crate) fn format(mut self) {
-        // Skip leading blanks if they exist
-        while self.tokens.optional_space().is_some() {}
+        // Skip leading blanks if they exist. The line break stays a soft
+        // break, but there is nothing to separate with a space at the start
+        while let Some(peeked) = self.tokens.peek() {
+            match peeked.token {
+                TokenTree::Space(_) => {}
+                TokenTree::Newline(_) => self.fmt.newline(),
+                _ => break,
+            }
+            peeked.consume();
+        }
 
         while let Some(node) = self.tokens.next() {
             match node {
                 TokenTree::Space(space) => self.on_space(space),
                 TokenTree::Newline(_count) => self.on_newline(),
-                TokenTree::Raw(content) => self.fmt.raw(self.measured_str(content)),
-                TokenTree::Punct(punct) => self.on_punct(None, punct),
+                TokenTree::Raw { start, content } => self.on_raw(*start, content),
+                TokenTree::AnsiEscape(content) => self.fmt.raw(MeasuredStr::invisible(content)),
+                TokenTree::Punct(punct) => self.on_punct_token(None, *punct),
                 TokenTree::Group(group) => self.on_group(None, group),
                 TokenTree::Quoted(quoted) => self.on_quoted(quoted),
             }
         }
     }
 
+    fn on_raw(&mut self, start: usize, content: &'i str) {
+        if self.config.wrap_raw_after.is_empty() {
+            self.fmt.raw(self.input_str(start, content));
+            return;
+        }
+
+        let mut pieces = vec![];
+        let mut segment = Some((start, content));
+
+        while let Some((mut start, content)) = segment {
+            for piece in raw_pieces(content, &self.config.wrap_raw_after) {
+                pieces.push(self.input_str(start, piece));
+                start += piece.len();
+            }
+
+            segment = self.raw_continuation(content);
+        }
+
+        let [first, rest @ ..] = pieces.as_slice() else {
+            return;
+        };
+
+        if rest.is_empty() {
+            self.fmt.raw(*first);
+            return;
+        }
+
+        self.fmt.begin(BreakStyle::Compact, self.global_indent());
+        self.fmt.raw(*first);
+        self.fmt.indent(1);
+
+        for piece in rest {
+            self.fmt.soft_break();
+            self.fmt.raw(*piece);
+        }
+
+        self.fmt.indent(-1);
+        self.fmt.end();
+    }
+
+    /// Consumes the raw content on the next line if the previous raw content
+    /// was wrapped after one of the `wrap_raw_after` sequences. This way the
+    /// wrapped run is laid out the same way when the output is formatted again.
+    fn raw_continuation(&mut self, prev: &str) -> Option<(usize, &'i str)> {
+        if !self.config.wraps_after(prev) {
+            return None;
+        }
+
+        let mut tokens = self.tokens.tokens.clone();
+
+        let (TokenTree::Newline(_), Some(TokenTree::Raw { start, content })) =
+            (tokens.next()?, skip_blanks(&mut tokens))
+        else {
+            return None;
+        };
+
+        // The formatter never wraps in the middle of a run of the sequences
+        if sequence_at(content, &self.config.wrap_raw_after).is_some() {
+            return None;
+        }
+
+        self.tokens.tokens = tokens;
+
+        Some((*start, content))
+    }
+
     fn on_quoted(&mut self, quoted: &'i parsing::l2::Quoted<'i>) {
-        self.fmt.raw(self.measured_str(&quoted.config.opening));
+        let config = quoted.config;
+
+        // The content of an unclosed quote can't be split, because there is
+        // no closing delimiter to end the line with
+        let split_closing = config
+            .split_closing
+            .as_ref()
+            .filter(|_| quoted.closing.is_some());
 
-        for content in &quoted.content {
-            self.fmt.raw(self.measured_str(content.source()));
+        let Some(split_closing) = split_closing else {
+            let mut start = quoted.opening + config.opening.len();
+
+            self.fmt
+                .raw(self.input_str(quoted.opening, &config.opening));
+
+            for content in &quoted.content {
+                self.fmt.raw(self.input_str(start, content.source()));
+                start += content.source().len();
+            }
+
+            if let Some(closing) = quoted.closing {
+                self.fmt.raw(self.input_str(closing, &config.closing));
+            }
+            return;
+        };
+
+        let mut literals = vec![quoted];
+
+        while let Some(next) = literals
+            .last()
+            .and_then(|prev| self.quoted_continuation(prev))
+        {
+            literals.push(next);
+        }
+
+        let pieces: Vec<_> = literals
+            .iter()
+            .map(|quoted| quoted_pieces(&quoted.content))
+            .collect();
+
+        let opening = self.measured_str(&config.opening);
+        let splittable = literals.len() > 1
+            || pieces
+                .iter()
+                .flatten()
+                .any(|piece| matches!(piece, QuotedPiece::Split));
+
+        if splittable {
+            self.fmt.begin(BreakStyle::Compact, self.global_indent());
         }
 
-        if quoted.closed {
-            self.fmt.raw(self.measured_str(&quoted.config.closing));
+        self.fmt.raw(opening.with_source(quoted.opening));
+
+        if splittable {
+            self.fmt.indent(1);
         }
+
+        for (i, (quoted, pieces)) in literals.iter().zip(pieces).enumerate() {
+            // The concatenated literals are joined and may be split again
+            if i > 0 {
+                self.fmt.split(self.measured_str(split_closing), opening);
+            }
+
+            let mut start = quoted.opening + config.opening.len();
+
+            for piece in pieces {
+                match piece {
+                    QuotedPiece::Text(text) => {
+                        self.fmt.raw(self.input_str(start, text));
+                        start += text.len();
+                    }
+                    QuotedPiece::Split => {
+                        self.fmt.split(self.measured_str(split_closing), opening);
+                    }
+                }
+            }
+        }
+
+        if let Some(closing) = literals.last().and_then(|quoted| quoted.closing) {
+            self.fmt.raw(self.input_str(closing, &config.closing));
+        }
+
+        if splittable {
+            self.fmt.indent(-1);
+            self.fmt.end();
+        }
+    }
+
+    /// Consumes the next quoted content if it's concatenated with the previous
+    /// one where the formatter could have split it. This way the split quoted
+    /// content is laid out the same way when the output is formatted again.
+    fn quoted_continuation(
+        &mut self,
+        prev: &parsing::l2::Quoted<'i>,
+    ) -> Option<&'i parsing::l2::Quoted<'i>> {
+        let operator = concat_operator(prev.config)?;
+        let mut tokens = self.tokens.tokens.clone();
+
+        if !operator.is_empty() {
+            let token = match skip_blanks(&mut tokens)? {
+                TokenTree::Raw { content, .. } => content,
+                TokenTree::Punct(punct) => &*punct.config.symbol,
+                _ => return None,
+            };
+
+            if token != operator {
+                return None;
+            }
+        }
+
+        let Some(TokenTree::Quoted(next)) = skip_blanks(&mut tokens) else {
+            return None;
+        };
+
+        if !std::ptr::eq(next.config, prev.config)
+            || prev.closing.is_none()
+            || next.closing.is_none()
+            || !is_split_point(&prev.content, &next.content)
+        {
+            return None;
+        }
+
+        self.tokens.tokens = tokens;
+
+        Some(next)
     }
 
     fn on_newline(&mut self) {
-        self.fmt.soft_break();
+        self.fmt.newline();
+
+        // The punct or the group decides the size of the space before it the
+        // same way as if the newline was a regular space
+        if let Some(TokenTree::Punct(_) | TokenTree::Group(_)) =
+            self.tokens.peek().map(|peeked| peeked.token)
+        {
+            self.on_space(" ");
+            return;
+        }
+
         self.fmt.space(1);
     }
 
@@ -78,7 +332,7 @@ impl<'i> FormattingCtx<'_, 'i> {
         match peeked.token {
             TokenTree::Punct(punct) => {
                 peeked.consume();
-                self.on_punct(Some(space), punct);
+                self.on_punct_token(Some(space), *punct);
             }
             TokenTree::Group(group) => {
                 peeked.consume();
@@ -88,13 +342,26 @@ impl<'i> FormattingCtx<'_, 'i> {
         }
     }
 
+    fn global_indent(&self) -> MeasuredStr<'i> {
+        self.measured_str(&self.config.indent)
+    }
+
+    /// Content copied from the given offset in the input as is
+    fn input_str<'a>(&self, start: usize, str: &'a str) -> MeasuredStr<'a> {
+        self.measured_str(str).with_source(start)
+    }
+
     fn measured_str<'a>(&self, str: &'a str) -> MeasuredStr<'a> {
-        self.config.visual_size.measured_str(str)
+        self.config
+            .visual_size
+            .measured_str(str, self.config.tab_width)
     }
 
     fn on_group(&mut self, leading_space: Option<&'i str>, group: &'i parsing::l2::Group<'i>) {
         let config = group.config;
 
+        let row = self.table.as_mut().and_then(|table| table.row(group));
+
         let is_empty_group = group
             .content
             .iter()
@@ -106,57 +373,156 @@ impl<'i> FormattingCtx<'_, 'i> {
             return;
         }
 
-        self.fmt.begin(group.config.break_style.0);
+        // The padding of the row depends on the break state of the table, so
+        // it must be added before the row's group begins
+        if let Some(row) = &row {
+            self.fmt.padding(row.prefix_padding, 0);
+        }
+
+        let break_style = config.break_style.0;
+        let indent = match &config.indent {
+            Some(indent) => self.measured_str(indent),
+            None => self.global_indent(),
+        };
+
+        self.fmt.begin(break_style, indent);
+
+        let force_break = match break_style {
+            BreakStyle::ConsistentIfMoreThan(max_items) => items_count(group) > max_items,
+            _ => false,
+        };
+
+        if force_break {
+            self.fmt.force_break();
+        }
 
         let mut tokens = group.content.iter();
 
-        let closing_punct_leading_blank = tokens
+        let trailing_blank = tokens
             .clone()
             .next_back()
-            .and_then(token_tree_to_space)
+            .filter(|token| matches!(token, TokenTree::Space(_) | TokenTree::Newline(_)))
             .inspect(|_| _ = tokens.next_back());
 
+        // The trailing newline of an unclosed group is moved out of it, as if
+        // it was followed by the closing punct. Otherwise, the dedent of the
+        // enclosing group would be placed before the newline.
+        let unclosed_trailing_newline =
+            group.closing().is_none() && matches!(trailing_blank, Some(TokenTree::Newline(_)));
+
+        // The newline before the closing punct is handled as a regular space
+        let closing_punct_leading_blank =
+            trailing_blank.map(|token| token_tree_to_space(token).unwrap_or(" "));
+
         let mut content = FormattingCtx {
             config: self.config,
             fmt: &mut *self.fmt,
             tokens: TokensCursor { tokens },
+            alignment: Alignment::new(self.config, group),
+            table: Table::new(self.config, group),
+            columns: row.map(|row| row.columns),
         };
 
-        content.on_punct(leading_space, &config.opening);
-        content.fmt.indent(1);
+        // With the aligned indent style the content is never separated from
+        // the delimiters with a line break
+        let block = config.indent_style.0 == IndentStyle::Block;
+
+        content.on_punct_with_breaks(leading_space, group.opening(), (true, block));
+
+        if block {
+            content.fmt.indent(1);
+        } else {
+            content.fmt.align_begin();
+        }
+
         content.format();
-        self.fmt.indent(-1);
 
-        if group.closed {
-            self.on_punct(closing_punct_leading_blank, &config.closing);
+        if block {
+            self.fmt.indent(-1);
+        } else {
+            self.fmt.align_end();
+        }
+
+        if let Some(closing) = group.closing() {
+            self.on_punct_with_breaks(closing_punct_leading_blank, closing, (block, true));
         }
 
         self.fmt.end();
+
+        if unclosed_trailing_newline {
+            self.on_newline();
+        }
     }
 
     // Special case for an empty group where we don't want any internal space,
     // and instead have a pair of adjacent opening and closing punctuation.
     fn empty_group(&mut self, leading_space: Option<&'i str>, group: &parsing::l2::Group<'i>) {
         let config = &group.config;
-        self.space_near_punct(leading_space, &config.opening.leading_space);
-        self.fmt.raw(self.measured_str(&config.opening.symbol));
+        self.space_near_punct(leading_space, &config.opening.leading_space, true);
+        self.fmt
+            .raw(self.input_str(group.opening, &config.opening.symbol));
 
-        if group.closed {
-            self.fmt.raw(self.measured_str(&config.closing.symbol));
+        if let Some(closing) = group.closing {
+            self.fmt
+                .raw(self.input_str(closing, &config.closing.symbol));
             let trailing_space = self.tokens.optional_space();
-            self.space_near_punct(trailing_space, &config.closing.trailing_space);
+            self.space_near_punct(trailing_space, &config.closing.trailing_space, true);
         }
     }
 
-    fn on_punct(&mut self, leading_space: Option<&'i str>, punct: &'i crate::Punct) {
-        self.space_near_punct(leading_space, &punct.leading_space);
-        self.fmt.raw(self.measured_str(&punct.symbol));
+    /// Handles the punct that is part of the content (not a group delimiter).
+    fn on_punct_token(&mut self, leading_space: Option<&'i str>, punct: parsing::l2::Punct<'i>) {
+        self.on_punct(leading_space, punct);
+
+        let padding = self
+            .alignment
+            .as_mut()
+            .and_then(|alignment| alignment.on_punct(punct.config));
+
+        if let Some(padding) = padding {
+            self.fmt.padding(padding, 0);
+        }
+
+        // Row's fields are aligned only if the table that contains the row is
+        // broken, which is one level above the row's group
+        if let Some(padding) = self.columns.as_mut().and_then(Columns::on_punct) {
+            self.fmt.padding(padding, 1);
+        }
+    }
+
+    fn on_punct(&mut self, leading_space: Option<&'i str>, punct: parsing::l2::Punct<'i>) {
+        self.on_punct_with_breaks(leading_space, punct, (true, true));
+    }
+
+    /// Same as [`Self::on_punct`], but the breakable spaces around the punct
+    /// turn into line breaks only if allowed by the `(leading, trailing)` flags.
+    fn on_punct_with_breaks(
+        &mut self,
+        leading_space: Option<&'i str>,
+        punct: parsing::l2::Punct<'i>,
+        (leading_break, trailing_break): (bool, bool),
+    ) {
+        let config = punct.config;
+
+        self.space_near_punct(leading_space, &config.leading_space, leading_break);
+        self.fmt.raw(self.input_str(punct.start, &config.symbol));
 
         let trailing_space = self.tokens.optional_space();
-        self.space_near_punct(trailing_space, &punct.trailing_space);
+
+        if self.tokens.peek().is_none() {
+            // No need for trailing blanks
+            return;
+        }
+
+        self.space_near_punct(trailing_space, &config.trailing_space, trailing_break);
     }
 
-    fn space_near_punct(&mut self, input: Option<&'i str>, config: &'i crate::Space) {
+    fn space_near_punct(
+        &mut self,
+        input: Option<&'i str>,
+        config: &'i crate::Space,
+        allow_break: bool,
+    ) {
         let input = input.unwrap_or("");
 
         let (min, max) = config.size;
@@ -165,10 +531,13 @@ impl<'i> FormattingCtx<'_, 'i> {
             min
         } else {
             // Preserve the size from input, but clamp it to the range
-            self.config.visual_size.measure(input).clamp(min, max)
+            self.config
+                .visual_size
+                .measure(input, self.config.tab_width)
+                .clamp(min, max)
         };
 
-        if config.breakable {
+        if config.breakable && allow_break {
             self.fmt.soft_break();
         }
 
@@ -214,6 +583,156 @@ impl<'t, 'i> Peeked<'t, 'i> {
     }
 }
 
+/// Counts the items of the group separated by the puncts with a breakable space
+fn items_count(group: &parsing::l2::Group<'_>) -> usize {
+    let is_separator = |token: &TokenTree<'_>| {
+        matches!(
+            token,
+            TokenTree::Punct(punct)
+                if punct.config.leading_space.breakable || punct.config.trailing_space.breakable
+        )
+    };
+
+    group
+        .content
+        .split(is_separator)
+        .filter(|item| !trim_blanks(item).is_empty())
+        .count()
+}
+
+enum QuotedPiece<'i> {
+    Text(&'i str),
+
+    /// A place where the quoted content may be split into several literals
+    Split,
+}
+
+/// Splits the quoted content after the runs of whitespace and after the
+/// escaped line breaks. There is never a split at the start or at the end of
+/// the content.
+fn quoted_pieces<'i>(content: &[QuotedContent<'i>]) -> Vec<QuotedPiece<'i>> {
+    let mut pieces = vec![];
+    let mut split_pending = false;
+
+    // Leading whitespace doesn't produce a split
+    let mut has_content = false;
+
+    for token in content {
+        match token {
+            QuotedContent::Raw(text) => {
+                let mut start = 0;
+
+                for (i, char) in text.char_indices() {
+                    if char.is_whitespace() {
+                        split_pending = has_content;
+                        continue;
+                    }
+
+                    has_content = true;
+
+                    if !std::mem::take(&mut split_pending) {
+                        continue;
+                    }
+
+                    if start < i {
+                        pieces.push(QuotedPiece::Text(&text[start..i]));
+                    }
+
+                    pieces.push(QuotedPiece::Split);
+                    start = i;
+                }
+
+                if start < text.len() {
+                    pieces.push(QuotedPiece::Text(&text[start..]));
+                }
+            }
+            QuotedContent::Escape(escape) => {
+                if std::mem::take(&mut split_pending) {
+                    pieces.push(QuotedPiece::Split);
+                }
+
+                pieces.push(QuotedPiece::Text(escape.source));
+                has_content = true;
+
+                split_pending = matches!(escape.unescaped, Unescaped::Char('\n'));
+            }
+        }
+    }
+
+    pieces
+}
+
+/// Whether the formatter may split the quoted content between the given parts
+/// of it. See [`quoted_pieces`].
+fn is_split_point(prev: &[QuotedContent<'_>], next: &[QuotedContent<'_>]) -> bool {
+    let has_content = prev.iter().any(|token| match token {
+        QuotedContent::Raw(text) => text.contains(|char: char| !char.is_whitespace()),
+        QuotedContent::Escape(_) => true,
+    });
+
+    let split_after = match prev.last() {
+        Some(QuotedContent::Raw(text)) => has_content && text.ends_with(char::is_whitespace),
+        Some(QuotedContent::Escape(escape)) => {
+            matches!(escape.unescaped, Unescaped::Char('\n'))
+        }
+        None => false,
+    };
+
+    let split_before = match next.first() {
+        Some(QuotedContent::Raw(text)) => !text.starts_with(char::is_whitespace),
+        Some(QuotedContent::Escape(_)) => true,
+        None => false,
+    };
+
+    split_after && split_before
+}
+
+/// Splits the raw content after the runs of the given sequences, where the
+/// longest sequence is matched at every position. A run is never split, so
+/// `://` stays intact if `/` and `:` are given. There is never a split at the
+/// end of the content.
+fn raw_pieces<'i>(content: &'i str, wrap_after: &[crate::Str]) -> Vec<&'i str> {
+    let matched_at = |i: usize| sequence_at(&content[i..], wrap_after);
+
+    let mut pieces = vec![];
+    let mut start = 0;
+    let mut i = 0;
+
+    while i < content.len() {
+        let matched = matched_at(i);
+
+        let Some(matched) = matched else {
+            i += content[i..].chars().next().map_or(1, char::len_utf8);
+            continue;
+        };
+
+        i += matched;
+
+        if i < content.len() && matched_at(i).is_none() {
+            pieces.push(&content[start..i]);
+            start = i;
+        }
+    }
+
+    pieces.push(&content[start..]);
+    pieces
+}
+
+/// Size of the longest of the given sequences at the start of the content
+fn sequence_at(content: &str, sequences: &[crate::Str]) -> Option<usize> {
+    sequences
+        .iter()
+        .map(|sequence| &**sequence)
+        .filter(|sequence| !sequence.is_empty() && content.starts_with(sequence))
+        .map(str::len)
+        .max()
+}
+
+/// Returns the next token that isn't a space or a newline
+fn skip_blanks<'i>(tokens: &mut std::slice::Iter<'i, TokenTree<'i>>) -> Option<&'i TokenTree<'i>> {
+    tokens.find(|token| !matches!(token, TokenTree::Space(_) | TokenTree::Newline(_)))
+}
+
 fn token_tree_to_space<'i>(token: &'i TokenTree<'i>) -> Option<&'i str> {
     match token {
         TokenTree::Space(space) => Some(space),
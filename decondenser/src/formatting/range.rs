@@ -0,0 +1,54 @@
+use crate::parsing::l2::{self, TokenTree};
+use crate::{Decondenser, TextEdit};
+use std::ops::Range;
+
+impl Decondenser {
+    pub(crate) fn format_range_impl(&self, input: &str, range: Range<usize>) -> TextEdit {
+        let tokens = l2::parse(self, input);
+
+        let Some(group) = enclosing_group(&tokens, &range) else {
+            return TextEdit {
+                range: 0..input.len(),
+                new_text: self.format(input),
+            };
+        };
+
+        let line_start = input[..group.start]
+            .rfind('\n')
+            .map_or(0, |index| index + 1);
+        let line_prefix = &input[line_start..group.start];
+
+        let line_indent =
+            &line_prefix[..line_prefix.len() - line_prefix.trim_start_matches([' ', '\t']).len()];
+
+        let start_column = self
+            .visual_size
+            .advance_column(0, line_prefix, self.tab_width);
+
+        let (new_text, _) = self.format_at(&input[group.clone()], start_column, line_indent);
+
+        TextEdit {
+            range: group,
+            new_text,
+        }
+    }
+}
+
+/// Returns the span of the innermost group that contains the range entirely.
+/// Unclosed groups are ignored, because their content extends to the end of
+/// the parent group.
+fn enclosing_group(tokens: &[TokenTree<'_>], range: &Range<usize>) -> Option<Range<usize>> {
+    tokens.iter().find_map(|token| {
+        let TokenTree::Group(group) = token else {
+            return None;
+        };
+
+        let end = group.closing? + group.config.closing.symbol.len();
+
+        if range.start < group.opening || range.end > end {
+            return None;
+        }
+
+        Some(enclosing_group(&group.content, range).unwrap_or(group.opening..end))
+    })
+}
@@ -0,0 +1,96 @@
+use crate::parsing::l2::{self, QuotedContent, TokenTree};
+use crate::parsing::quoted::l1::Unescaped;
+use crate::{Decondenser, FormatError};
+
+impl Decondenser {
+    /// Rejects the malformed input when the [`Decondenser::strict()`] mode is
+    /// enabled. Returns the first problem in the order of the input.
+    pub(crate) fn check_strict(&self, input: &str) -> Result<(), FormatError> {
+        match self.diagnostics_impl(input).into_iter().next() {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+
+    pub(crate) fn diagnostics_impl(&self, input: &str) -> Vec<FormatError> {
+        let tokens = l2::parse(self, input);
+        let mut problems = vec![];
+        self.check_tokens(&tokens, &mut problems);
+        problems
+    }
+
+    fn check_tokens(&self, tokens: &[TokenTree<'_>], problems: &mut Vec<FormatError>) {
+        for token in tokens {
+            match token {
+                TokenTree::Space(_)
+                | TokenTree::Newline(_)
+                | TokenTree::AnsiEscape(_)
+                | TokenTree::Punct(_) => {}
+                TokenTree::Raw { start, content } => self.check_raw(*start, content, problems),
+                TokenTree::Group(group) => {
+                    if group.closing.is_none() {
+                        problems.push(FormatError::UnclosedGroup {
+                            offset: group.opening,
+                            opening: group.config.opening.symbol.to_string(),
+                        });
+                    }
+
+                    self.check_tokens(&group.content, problems);
+                }
+                TokenTree::Quoted(quoted) => check_quoted(quoted, problems),
+            }
+        }
+    }
+
+    /// The closing sequences of the groups are parsed as raw content if
+    /// there is no opening sequence that they could match.
+    fn check_raw(&self, start: usize, content: &str, problems: &mut Vec<FormatError>) {
+        let mut rest = 0;
+
+        while let Some((offset, closing)) = self.find_closing(&content[rest..]) {
+            problems.push(FormatError::UnmatchedClosing {
+                offset: start + rest + offset,
+                closing: closing.to_owned(),
+            });
+
+            rest += offset + closing.len();
+        }
+    }
+
+    /// Finds the first closing sequence in the content. The longest one wins
+    /// if several of them start at the same offset.
+    fn find_closing(&self, content: &str) -> Option<(usize, &str)> {
+        self.groups
+            .iter()
+            .filter_map(|group| {
+                let closing = &*group.closing.symbol;
+                let offset = content.find(closing).filter(|_| !closing.is_empty())?;
+                Some((offset, closing))
+            })
+            .min_by_key(|&(offset, closing)| (offset, std::cmp::Reverse(closing.len())))
+    }
+}
+
+fn check_quoted(quoted: &l2::Quoted<'_>, problems: &mut Vec<FormatError>) {
+    if quoted.closing.is_none() {
+        problems.push(FormatError::UnclosedQuote {
+            offset: quoted.opening,
+            opening: quoted.config.opening.to_string(),
+        });
+    }
+
+    let mut offset = quoted.opening + quoted.config.opening.len();
+
+    for content in &quoted.content {
+        if let QuotedContent::Escape(escape) = content {
+            if matches!(escape.unescaped, Unescaped::Invalid) {
+                problems.push(FormatError::InvalidEscape {
+                    offset,
+                    escape: escape.source.to_owned(),
+                });
+            }
+        }
+
+        offset += content.source().len();
+    }
+}
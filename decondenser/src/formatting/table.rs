@@ -0,0 +1,151 @@
+use super::measure::{Measure, trim_blanks};
+use crate::config::BreakStyleEnum as BreakStyle;
+use crate::parsing::l2::{Group, TokenTree};
+use crate::{Decondenser, Punct, config};
+use std::collections::VecDeque;
+
+/// Layout of the group with [`BreakStyle::table()`] where every item is a
+/// nested group (a row) of the same shape.
+///
+/// The content of each row is split into fields by its puncts, and every field
+/// is padded to the size of the largest field in the same column.
+///
+/// [`BreakStyle::table()`]: crate::BreakStyle::table()
+pub(super) struct Table<'i> {
+    rows: VecDeque<Row<'i>>,
+}
+
+pub(super) struct Row<'i> {
+    group: &'i Group<'i>,
+
+    /// Padding before the opening punct of the row that aligns the rows with
+    /// prefixes of different sizes (e.g. type names).
+    pub(super) prefix_padding: usize,
+
+    pub(super) columns: Columns,
+}
+
+/// Paddings that follow each punct directly inside of a single row in order.
+pub(super) struct Columns {
+    paddings: std::vec::IntoIter<usize>,
+}
+
+impl Columns {
+    /// Returns the padding that should follow the next punct of the row.
+    pub(super) fn on_punct(&mut self) -> Option<usize> {
+        self.paddings.next()
+    }
+}
+
+impl<'i> Table<'i> {
+    pub(super) fn new(config: &Decondenser, group: &'i Group<'i>) -> Option<Self> {
+        if group.config.break_style.0 != BreakStyle::Table {
+            return None;
+        }
+
+        let measure = Measure::new(config);
+
+        let items = group
+            .content
+            .split(|token| matches!(token, TokenTree::Punct(_)))
+            .map(trim_blanks)
+            .filter(|item| !item.is_empty());
+
+        let mut rows = vec![];
+        let mut shape: Option<(&config::Group, Vec<&Punct>)> = None;
+
+        for item in items {
+            let [prefix @ .., TokenTree::Group(row)] = item else {
+                return None;
+            };
+
+            if row.closing.is_none()
+                || prefix
+                    .iter()
+                    .any(|token| matches!(token, TokenTree::Group(_)))
+            {
+                return None;
+            }
+
+            let row_puncts = row.content.iter().filter_map(|token| match token {
+                TokenTree::Punct(punct) => Some(punct.config),
+                _ => None,
+            });
+
+            // All rows must have the same delimiters and the same sequence of
+            // puncts inside, otherwise there are no columns to align.
+            match &shape {
+                Some((config, puncts)) => {
+                    let same_shape = std::ptr::eq(*config, row.config)
+                        && row_puncts.clone().count() == puncts.len()
+                        && row_puncts
+                            .zip(puncts)
+                            .all(|(punct, expected)| std::ptr::eq(punct, *expected));
+
+                    if !same_shape {
+                        return None;
+                    }
+                }
+                None => shape = Some((row.config, row_puncts.collect())),
+            }
+
+            let prefix_size = prefix.iter().map(|token| measure.token(token)).sum();
+
+            // The last field isn't followed by any punct, so it's not padded
+            let mut fields = row
+                .content
+                .split(|token| matches!(token, TokenTree::Punct(_)))
+                .map(|field| measure.tokens(field))
+                .collect::<Vec<_>>();
+
+            fields.pop();
+
+            rows.push((prefix_size, row, fields));
+        }
+
+        if rows.len() < 2 {
+            return None;
+        }
+
+        let max_prefix_size = rows.iter().map(|(prefix, ..)| *prefix).max().unwrap_or(0);
+
+        let column_sizes = rows.iter().fold(vec![], |mut column_sizes, (.., fields)| {
+            column_sizes.resize(fields.len(), 0);
+            for (column_size, field) in column_sizes.iter_mut().zip(fields) {
+                *column_size = std::cmp::max(*column_size, *field);
+            }
+            column_sizes
+        });
+
+        let rows = rows
+            .into_iter()
+            .map(|(prefix_size, group, mut fields)| {
+                // Turn field sizes into paddings in-place
+                for (field, column_size) in fields.iter_mut().zip(&column_sizes) {
+                    *field = column_size - *field;
+                }
+
+                Row {
+                    group,
+                    prefix_padding: max_prefix_size - prefix_size,
+                    columns: Columns {
+                        paddings: fields.into_iter(),
+                    },
+                }
+            })
+            .collect();
+
+        Some(Self { rows })
+    }
+
+    /// Returns the layout of the given group if it's the next row of the table.
+    pub(super) fn row(&mut self, group: &Group<'_>) -> Option<Row<'i>> {
+        let row = self.rows.front()?;
+
+        if !std::ptr::eq(row.group, group) {
+            return None;
+        }
+
+        self.rows.pop_front()
+    }
+}
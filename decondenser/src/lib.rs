@@ -3,27 +3,43 @@
 
 mod ansi;
 mod config;
+mod config_data;
 mod cursor;
+mod error;
 mod formatting;
 mod parsing;
 mod sealed;
+mod source_map;
 mod space;
 mod str;
+mod text_edit;
 mod utils;
+mod verify;
 mod visual_size;
 
 #[cfg(feature = "unstable")]
 mod unstable;
 
-pub use self::config::{BreakStyle, Group, Punct, Quote};
+pub use self::config::{BreakStyle, Group, IndentStyle, LayoutAlgorithm, Punct, Quote};
+pub use self::config_data::{
+    DecondenserConfig, GroupConfig, PunctConfig, QuoteConfig, SpaceConfig, VisualSizeConfig,
+};
+pub use self::error::FormatError;
 pub use self::parsing::quoted::unescape;
+pub use self::source_map::{Mapping, SourceMap};
 pub use self::space::{IntoSpace, Space, SpaceSize};
 pub use self::str::IntoStr;
-pub use self::visual_size::VisualSize;
+pub use self::text_edit::TextEdit;
+pub use self::verify::IdempotencyError;
+pub use self::visual_size::{CharCount, VisualSize};
+
+#[cfg(feature = "unicode-width")]
+pub use self::visual_size::{GraphemeCount, UnicodeWidth};
 
 use self::sealed::Sealed;
 use self::str::Str;
 use self::visual_size::ArcVisualSize;
+use std::ops::Range;
 
 /// Provide configuration and run [`Decondenser::format()`] to format the
 /// input.
@@ -31,21 +47,21 @@ use self::visual_size::ArcVisualSize;
 #[must_use = "Decondenser doesn't produce side effects. Make sure to call `decondense()` to use it"]
 pub struct Decondenser {
     indent: Str,
+    tab_width: usize,
     max_line_size: usize,
     no_break_size: Option<usize>,
     groups: Vec<Group>,
     puncts: Vec<Punct>,
     quotes: Vec<Quote>,
+    wrap_raw_after: Vec<Str>,
     escape_char: char,
     visual_size: ArcVisualSize,
+    layout_algorithm: LayoutAlgorithm,
+    strict: bool,
     debug_layout: bool,
     debug_indent: bool,
 }
 
-fn default_visual_size(str: &str) -> usize {
-    str.chars().filter(|&char| char != '\r').count()
-}
-
 impl Decondenser {
     /// Creates an empty [`Decondenser`] instance without any groups, quotes, or
     /// punctuation sequences configured. It is only useful as a starting point
@@ -55,23 +71,23 @@ impl Decondenser {
     pub fn empty() -> Self {
         Self {
             indent: Str::n_spaces(4),
+            tab_width: 4,
             max_line_size: 80,
             no_break_size: None,
             groups: vec![],
             puncts: vec![],
             quotes: vec![],
+            wrap_raw_after: vec![],
 
-            // Not sure if it makes sense to make this configurable, and if so
-            // what the best and flexible-enough API for this would be. By
-            // making this configurable we'd likely want to make the escapes
-            // parsing algorithm itself configurable in general. Maybe as a
-            // closure parameter?
+            // Only configurable via the unstable API for now. Not sure what the
+            // best and flexible-enough API for this would be. We'd likely want
+            // to make the escapes parsing algorithm itself configurable in
+            // general. Maybe as a closure parameter?
             escape_char: '\\',
 
-            // Not using closure syntax here for the `default_visual_size` to
-            // make its type name (that is used in `VisualSizeAlgorithm` Debug
-            // impl) much nicer.
-            visual_size: ArcVisualSize::new(default_visual_size),
+            visual_size: ArcVisualSize::new(CharCount),
+            layout_algorithm: LayoutAlgorithm::oppen(),
+            strict: false,
             debug_layout: false,
             debug_indent: false,
         }
@@ -129,13 +145,87 @@ impl Decondenser {
     ///
     /// There is no guarantee that the output will not contain lines longer than
     /// this size. For example, a single long string literal or a long sequence
-    /// of non-whitespace characters may span more than this many characters,
-    /// and decondenser does not currently attempt to break these up.
+    /// of non-whitespace characters may span more than this many characters.
+    /// Breaking these up is opt-in via [`Quote::concat_operator()`] and
+    /// [`Decondenser::wrap_raw_after()`].
     #[must_use]
     pub fn format(&self, input: &str) -> String {
+        self.format_impl(input).0
+    }
+
+    /// Same as [`Decondenser::format()`], but also returns the [`SourceMap`]
+    /// between the output and the input. It's useful for editor integrations
+    /// that need to preserve the cursor position or the diagnostics positions
+    /// when the text is formatted.
+    #[must_use]
+    pub fn format_with_map(&self, input: &str) -> (String, SourceMap) {
         self.format_impl(input)
     }
 
+    /// Formats only the innermost group that encloses the given byte range of
+    /// the input, and returns the [`TextEdit`] that replaces that group. The
+    /// group is formatted at the indentation level of the line where it
+    /// starts, so the rest of the input stays untouched.
+    ///
+    /// If the range isn't inside of any group, the whole input is formatted.
+    #[must_use]
+    pub fn format_range(&self, input: &str, range: Range<usize>) -> TextEdit {
+        self.format_range_impl(input, range)
+    }
+
+    /// Same as [`Decondenser::format()`], but also checks that the output has
+    /// the same non-whitespace content as the input, i.e. the formatting only
+    /// changed the whitespace. The check is skipped if the debug output of the
+    /// layout or indentation is enabled, because it inserts extra characters.
+    ///
+    /// In the [`Decondenser::strict()`] mode the malformed input is rejected
+    /// before formatting.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`FormatError::ContentChanged`] if the check fails, which is
+    /// always a bug in decondenser. In the [`Decondenser::strict()`] mode
+    /// returns the other [`FormatError`] variants for the malformed input.
+    pub fn try_format(&self, input: &str) -> Result<String, FormatError> {
+        if self.strict {
+            self.check_strict(input)?;
+        }
+
+        let output = self.format(input);
+
+        if !self.debug_layout && !self.debug_indent {
+            self.check_equivalence(input, &output)?;
+        }
+
+        Ok(output)
+    }
+
+    /// Returns all problems of the malformed input that the
+    /// [`Decondenser::strict()`] mode would reject, in the order of the input.
+    /// It doesn't depend on whether the strict mode is enabled.
+    ///
+    /// The input is formatted on a best-effort basis regardless of these
+    /// problems, so this is useful to explain the unexpected output to the
+    /// user. Use [`FormatError::range()`] to highlight the problems.
+    #[must_use]
+    pub fn diagnostics(&self, input: &str) -> Vec<FormatError> {
+        self.diagnostics_impl(input)
+    }
+
+    /// Formats the input, and then formats the output once again to check that
+    /// the formatting is idempotent, i.e. the already formatted output doesn't
+    /// change. Returns the output of the first pass if it's the case, or the
+    /// first line where the outputs diverge otherwise.
+    ///
+    /// This is mostly useful for testing the configuration and reporting bugs.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`IdempotencyError`] if the second pass changes the output.
+    pub fn verify(&self, input: &str) -> Result<String, IdempotencyError> {
+        self.verify_impl(input)
+    }
+
     /// String to used to make a single level of indentation.
     ///
     /// Defaults to 4 spaces.
@@ -144,6 +234,19 @@ impl Decondenser {
         self
     }
 
+    /// Number of columns between tab stops. A tab character in the
+    /// [`indent`] string or in the input advances the line to the next tab
+    /// stop when calculating the size of the line.
+    ///
+    /// Defaults to 4, so that an `indent` of `"\t"` produces the same layout
+    /// as the default indent of 4 spaces.
+    ///
+    /// [`indent`]: Self::indent()
+    pub fn tab_width(mut self, value: usize) -> Self {
+        self.tab_width = value;
+        self
+    }
+
     /// Best-effort max size of a line to fit into.
     ///
     /// The resulting output will try to fit into this many characters per line,
@@ -177,13 +280,22 @@ impl Decondenser {
 
     /// Algorithm used to calculate the effective "visual" size of a string.
     ///
-    /// The default algorithm uses [`str::chars()`] to count the number of
-    /// [`char`]s in the string with the exception of `\r` characters. It
-    /// doesn't take into account printable/non-printable characters other than
-    /// that.
+    /// The default algorithm is [`CharCount`]. It uses [`str::chars()`] to count
+    /// the number of [`char`]s in the string with the exception of `\r`
+    /// characters and ANSI escape sequences (e.g. color codes like `\x1b[31m`).
+    /// It doesn't take into account printable/non-printable characters other
+    /// than that.
+    ///
+    /// ANSI escape sequences that appear outside of quoted content are always
+    /// considered to have zero size regardless of the configured algorithm.
+    ///
+    /// For more robust size calculation, enable the `unicode-width` cargo
+    /// feature of this crate and use `UnicodeWidth` (terminal display width)
+    /// or `GraphemeCount` (number of user-perceived characters) algorithms.
     ///
-    /// For more robust size calculation, the crate [`unicode_width`] can be
-    /// used like this ([`VisualSize`] is implemented for `Fn(&str) -> usize`):
+    /// Any other algorithm can be plugged in as well, since [`VisualSize`] is
+    /// implemented for `Fn(&str) -> usize`. For example, with the
+    /// [`unicode_width`] crate:
     ///
     /// ```
     /// # use decondenser::Decondenser;
@@ -206,6 +318,30 @@ impl Decondenser {
         self
     }
 
+    /// Algorithm used to decide where to place line breaks.
+    ///
+    /// Defaults to [`LayoutAlgorithm::oppen()`], which is fast and streaming.
+    /// [`LayoutAlgorithm::wadler()`] may produce more compact output at the
+    /// cost of buffering the entire input.
+    pub fn layout_algorithm(mut self, value: LayoutAlgorithm) -> Self {
+        self.layout_algorithm = value;
+        self
+    }
+
+    /// Makes [`Decondenser::try_format()`] reject the input with unclosed
+    /// groups or quotes, unmatched closing sequences of groups, or invalid
+    /// escapes in the quoted content. Useful for formatting untrusted input
+    /// where such problems should be reported instead of formatted as is.
+    ///
+    /// [`Decondenser::format()`] isn't affected by this and always formats the
+    /// input on the best-effort basis.
+    ///
+    /// Defaults to `false`.
+    pub fn strict(mut self, value: bool) -> Self {
+        self.strict = value;
+        self
+    }
+
     /// Set group characters that are used to nest content.
     pub fn groups(mut self, value: impl IntoIterator<Item = Group>) -> Self {
         self.groups = Vec::from_iter(value);
@@ -223,6 +359,78 @@ impl Decondenser {
         self.quotes = Vec::from_iter(value);
         self
     }
+
+    /// Appends the groups to the already configured ones. A group with the
+    /// same opening sequence as an already configured group replaces it in
+    /// place, so that its parsing priority is preserved.
+    pub fn add_groups(mut self, value: impl IntoIterator<Item = Group>) -> Self {
+        for group in value {
+            utils::upsert(&mut self.groups, group, |group| &group.opening.symbol);
+        }
+        self
+    }
+
+    /// Removes the groups with the given opening sequences.
+    pub fn remove_groups(mut self, openings: impl IntoIterator<Item = impl IntoStr>) -> Self {
+        let openings = openings.into_iter().map(Str::new).collect::<Vec<_>>();
+        self.groups.retain(|group| {
+            !openings
+                .iter()
+                .any(|opening| **opening == *group.opening.symbol)
+        });
+        self
+    }
+
+    /// Appends the punctuation sequences to the already configured ones. Same
+    /// as [`Decondenser::add_groups()`], a [`Punct`] with the same symbol as
+    /// an already configured one replaces it in place.
+    pub fn add_puncts(mut self, value: impl IntoIterator<Item = Punct>) -> Self {
+        for punct in value {
+            utils::upsert(&mut self.puncts, punct, |punct| &punct.symbol);
+        }
+        self
+    }
+
+    /// Removes the punctuation sequences with the given symbols.
+    pub fn remove_puncts(mut self, symbols: impl IntoIterator<Item = impl IntoStr>) -> Self {
+        let symbols = symbols.into_iter().map(Str::new).collect::<Vec<_>>();
+        self.puncts
+            .retain(|punct| !symbols.iter().any(|symbol| **symbol == *punct.symbol));
+        self
+    }
+
+    /// Appends the quotes to the already configured ones. Same as
+    /// [`Decondenser::add_groups()`], a [`Quote`] with the same opening
+    /// delimiter as an already configured one replaces it in place.
+    pub fn add_quotes(mut self, value: impl IntoIterator<Item = Quote>) -> Self {
+        for quote in value {
+            utils::upsert(&mut self.quotes, quote, |quote| &quote.opening);
+        }
+        self
+    }
+
+    /// Removes the quotes with the given opening delimiters.
+    pub fn remove_quotes(mut self, openings: impl IntoIterator<Item = impl IntoStr>) -> Self {
+        let openings = openings.into_iter().map(Str::new).collect::<Vec<_>>();
+        self.quotes
+            .retain(|quote| !openings.iter().any(|opening| **opening == *quote.opening));
+        self
+    }
+
+    /// Sequences after which a long run of non-whitespace characters may be
+    /// wrapped to the next line if it doesn't fit on the line. Useful for URLs
+    /// and paths, e.g. `["/", ".", "::", "_"]`. The continuation lines are
+    /// indented. A run of these sequences is never wrapped in the middle.
+    ///
+    /// The raw content that ends with one of the sequences is joined with the
+    /// raw content on the next line, so the wrapped runs are laid out the same
+    /// way when the output is formatted again.
+    ///
+    /// Empty by default, so such runs are never wrapped.
+    pub fn wrap_raw_after(mut self, value: impl IntoIterator<Item = impl IntoStr>) -> Self {
+        self.wrap_raw_after = value.into_iter().map(Str::new).collect();
+        self
+    }
 }
 
 /// A trait used to specify "string-like" values (`&str`, `String`, etc.) and
@@ -2,6 +2,7 @@ mod token_tree;
 
 pub(crate) use token_tree::*;
 
+use crate::ansi;
 use crate::cursor::Cursor;
 use crate::{Decondenser, config};
 use std::mem;
@@ -23,12 +24,28 @@ struct Parser<'a> {
 }
 
 impl<'a> Parser<'a> {
-    fn parse(&mut self, terminator: Option<&str>) -> Option<usize> {
+    /// Parses the content of the given group (or the top level if `None`)
+    /// until the group's closing sequence.
+    fn parse(&mut self, group: Option<&'a config::Group>) -> Option<usize> {
+        let terminator = group.map(|group| &*group.closing.symbol);
+        let align_punct = group.and_then(|group| group.align_after.as_ref());
+
         while let Some(char) = self.cursor.peek() {
+            // The whole run of whitespace that contains a newline becomes a
+            // single newline token, so that the spaces around the newline
+            // don't change the way it's formatted
             if char == '\n' {
-                if !matches!(self.output.last(), Some(TokenTree::Newline { .. })) {
-                    let start = self.cursor.byte_offset();
-                    self.output.push(TokenTree::Newline { start });
+                match self.output.last_mut() {
+                    Some(TokenTree::Newline { .. }) => {}
+                    Some(last @ TokenTree::Space { .. }) => {
+                        *last = TokenTree::Newline {
+                            start: last.start(),
+                        };
+                    }
+                    _ => {
+                        let start = self.cursor.byte_offset();
+                        self.output.push(TokenTree::Newline { start });
+                    }
                 }
 
                 self.cursor.next();
@@ -36,7 +53,10 @@ impl<'a> Parser<'a> {
             }
 
             if char.is_whitespace() {
-                if !matches!(self.output.last(), Some(TokenTree::Space { .. })) {
+                if !matches!(
+                    self.output.last(),
+                    Some(TokenTree::Space { .. } | TokenTree::Newline { .. })
+                ) {
                     let start = self.cursor.byte_offset();
                     self.output.push(TokenTree::Space { start });
                 }
@@ -45,6 +65,17 @@ impl<'a> Parser<'a> {
                 continue;
             }
 
+            // ANSI escapes must be handled before anything else, because they
+            // can contain characters that look like groups or punctuation
+            // (e.g. `[` in `\x1b[31m`).
+            if let Some(len) = ansi::escape_sequence_len(self.cursor.as_str()) {
+                let sequence = &self.cursor.as_str()[..len];
+                if let Some(start) = self.cursor.strip_prefix(sequence) {
+                    self.output.push(TokenTree::AnsiEscape { start });
+                }
+                continue;
+            }
+
             if let Some(start) = terminator.and_then(|term| self.cursor.strip_prefix(term)) {
                 return Some(start);
             }
@@ -70,11 +101,23 @@ impl<'a> Parser<'a> {
                 continue;
             }
 
-            let punct = self
+            let global_punct = self
                 .config
                 .puncts
                 .iter()
-                .find_map(|punct| Some((punct, self.cursor.strip_prefix(&punct.symbol)?)));
+                .find(|punct| self.cursor.as_str().starts_with(&*punct.symbol));
+
+            // The `align_after` punct must not split a longer global punct
+            // that starts with it (e.g. `:` in `::`)
+            let align_punct = align_punct
+                .filter(|punct| self.cursor.as_str().starts_with(&*punct.symbol))
+                .filter(|align| {
+                    global_punct.is_none_or(|global| global.symbol.len() <= align.symbol.len())
+                });
+
+            let punct = align_punct
+                .or(global_punct)
+                .and_then(|punct| Some((punct, self.cursor.strip_prefix(&punct.symbol)?)));
 
             if let Some((config, start)) = punct {
                 self.output.push(TokenTree::Punct(Punct { start, config }));
@@ -95,7 +138,7 @@ impl<'a> Parser<'a> {
     fn parse_group(&mut self, opening: usize, config: &'a config::Group) {
         let prev = mem::take(&mut self.output);
 
-        let closing = self.parse(Some(&config.closing.symbol));
+        let closing = self.parse(Some(config));
 
         let group = Group {
             opening,
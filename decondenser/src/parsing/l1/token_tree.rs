@@ -9,7 +9,7 @@ pub(crate) enum TokenTree<'a> {
         start: usize,
     },
 
-    /// Represents 1 or many subsequent `[\r]\n` sequences
+    /// Consecutive whitespace characters that contain 1 or many line breaks
     Newline {
         start: usize,
     },
@@ -18,6 +18,12 @@ pub(crate) enum TokenTree<'a> {
     Raw {
         start: usize,
     },
+
+    /// A single ANSI escape sequence (e.g. a color code) that is invisible
+    /// when displayed in a terminal
+    AnsiEscape {
+        start: usize,
+    },
     Punct(Punct<'a>),
     Group(Group<'a>),
     Quoted(Quoted<'a>),
@@ -26,7 +32,10 @@ pub(crate) enum TokenTree<'a> {
 impl TokenTree<'_> {
     pub(crate) fn start(&self) -> usize {
         match self {
-            Self::Space { start } | Self::Newline { start } | Self::Raw { start } => *start,
+            Self::Space { start }
+            | Self::Newline { start }
+            | Self::Raw { start }
+            | Self::AnsiEscape { start } => *start,
             Self::Punct(punct) => punct.start,
             Self::Group(group) => group.opening,
             Self::Quoted(quoted) => quoted.opening,
@@ -40,6 +49,7 @@ impl fmt::Debug for TokenTree<'_> {
             Self::Space { start } => write!(f, "space {start}"),
             Self::Newline { start } => write!(f, "newline {start}"),
             Self::Raw { start } => write!(f, "raw {start}"),
+            Self::AnsiEscape { start } => write!(f, "ansi {start}"),
             Self::Punct(punct) => write!(f, "punct{punct:#?}"),
             Self::Group(group) => write!(f, "group{group:#?}"),
             Self::Quoted(quoted) => write!(f, "quoted{quoted:#?}"),
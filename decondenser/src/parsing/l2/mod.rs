@@ -36,15 +36,27 @@ impl<'a> TokenTreesFromL1<'a> {
 
                 match node {
                     l1::TokenTree::Space { start } => TokenTree::Space(&input[*start..end]),
-                    l1::TokenTree::Newline { start } => TokenTree::Newline(end - *start),
-                    l1::TokenTree::Raw { start } => TokenTree::Raw(&input[*start..end]),
-                    l1::TokenTree::Punct(punct) => TokenTree::Punct(punct.config),
+                    l1::TokenTree::Newline { start } => {
+                        TokenTree::Newline(input[*start..end].matches('\n').count())
+                    }
+                    l1::TokenTree::Raw { start } => TokenTree::Raw {
+                        start: *start,
+                        content: &input[*start..end],
+                    },
+                    l1::TokenTree::AnsiEscape { start } => {
+                        TokenTree::AnsiEscape(&input[*start..end])
+                    }
+                    l1::TokenTree::Punct(punct) => TokenTree::Punct(Punct {
+                        start: punct.start,
+                        config: punct.config,
+                    }),
                     l1::TokenTree::Group(group) => {
                         let content_end = group.closing.unwrap_or(end);
 
                         TokenTree::Group(Group {
+                            opening: group.opening,
                             content: self.convert(&group.content, content_end),
-                            closed: group.closing.is_some(),
+                            closing: group.closing,
                             config: group.config,
                         })
                     }
@@ -73,8 +85,9 @@ impl<'a> TokenTreesFromL1<'a> {
                             .collect();
 
                         TokenTree::Quoted(Quoted {
+                            opening: quoted.opening,
                             content,
-                            closed: quoted.closing.is_some(),
+                            closing: quoted.closing,
                             config: quoted.config,
                         })
                     }
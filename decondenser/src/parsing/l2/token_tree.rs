@@ -6,8 +6,9 @@ use std::fmt;
 pub(crate) enum TokenTree<'a> {
     Space(&'a str),
     Newline(usize),
-    Raw(&'a str),
-    Punct(&'a config::Punct),
+    Raw { start: usize, content: &'a str },
+    AnsiEscape(&'a str),
+    Punct(Punct<'a>),
     Group(Group<'a>),
     Quoted(Quoted<'a>),
 }
@@ -17,23 +18,35 @@ impl fmt::Debug for TokenTree<'_> {
         match self {
             Self::Space(text) => write!(f, "space {text:?}"),
             Self::Newline(count) => write!(f, "newline {count}"),
-            Self::Raw(text) => write!(f, "raw {text:?}"),
-            Self::Punct(punct) => write!(f, "punct {:?}", punct.symbol),
+            Self::Raw { start, content } => write!(f, "raw {start}: {content:?}"),
+            Self::AnsiEscape(text) => write!(f, "ansi {text:?}"),
+            Self::Punct(punct) => write!(f, "punct {}: {:?}", punct.start, punct.config.symbol),
             Self::Group(group) => write!(f, "group {group:?}"),
             Self::Quoted(quoted) => write!(f, "quoted {quoted:?}"),
         }
     }
 }
 
+#[derive(Clone, Copy)]
+pub(crate) struct Punct<'a> {
+    /// Offset of the punct in the input
+    pub(crate) start: usize,
+    pub(crate) config: &'a config::Punct,
+}
+
 pub(crate) struct Quoted<'a> {
+    /// Offset of the opening delimiter in the input
+    pub(crate) opening: usize,
     pub(crate) content: Vec<QuotedContent<'a>>,
-    pub(crate) closed: bool,
+
+    /// Offset of the closing delimiter in the input if the quote is closed
+    pub(crate) closing: Option<usize>,
     pub(crate) config: &'a config::Quote,
 }
 
 impl fmt::Debug for Quoted<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let closing = if self.closed {
+        let closing = if self.closing.is_some() {
             &self.config.closing
         } else {
             "{none}"
@@ -48,14 +61,34 @@ impl fmt::Debug for Quoted<'_> {
 }
 
 pub(crate) struct Group<'a> {
+    /// Offset of the opening delimiter in the input
+    pub(crate) opening: usize,
     pub(crate) content: Vec<TokenTree<'a>>,
-    pub(crate) closed: bool,
+
+    /// Offset of the closing delimiter in the input if the group is closed
+    pub(crate) closing: Option<usize>,
     pub(crate) config: &'a config::Group,
 }
 
+impl<'a> Group<'a> {
+    pub(crate) fn opening(&self) -> Punct<'a> {
+        Punct {
+            start: self.opening,
+            config: &self.config.opening,
+        }
+    }
+
+    pub(crate) fn closing(&self) -> Option<Punct<'a>> {
+        Some(Punct {
+            start: self.closing?,
+            config: &self.config.closing,
+        })
+    }
+}
+
 impl fmt::Debug for Group<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let closing = if self.closed {
+        let closing = if self.closing.is_some() {
             &self.config.closing.symbol
         } else {
             "{none}"
@@ -112,6 +112,7 @@ impl<'i> Lexer<'i> {
         };
 
         let unescaped = match char {
+            _ if char == self.escape_char => Unescaped::Char(char),
             'n' => Unescaped::Char('\n'),
             't' => Unescaped::Char('\t'),
             'r' => Unescaped::Char('\r'),
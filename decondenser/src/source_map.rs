@@ -0,0 +1,75 @@
+use std::cmp;
+use std::ops::Range;
+
+/// Mapping between the byte offsets of the formatted output and the input
+/// returned from [`Decondenser::format_with_map()`].
+///
+/// Every raw, punct and quoted token that is copied from the input to the
+/// output has a [`Mapping`]. The whitespace between the tokens isn't mapped,
+/// because it's rewritten by the formatter.
+///
+/// [`Decondenser::format_with_map()`]: crate::Decondenser::format_with_map()
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SourceMap {
+    mappings: Vec<Mapping>,
+}
+
+/// Range of bytes in the output copied from the range of bytes in the input.
+/// Both ranges are of the same length.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mapping {
+    /// Byte range in the formatted output
+    pub output: Range<usize>,
+
+    /// Byte range in the input the output was copied from
+    pub input: Range<usize>,
+}
+
+impl SourceMap {
+    pub(crate) fn push(&mut self, output: usize, input: usize, len: usize) {
+        self.mappings.push(Mapping {
+            output: output..output + len,
+            input: input..input + len,
+        });
+    }
+
+    /// Mappings ordered by their position in the output. The formatter never
+    /// reorders the tokens, so they are also ordered by the position in the
+    /// input.
+    pub fn mappings(&self) -> &[Mapping] {
+        &self.mappings
+    }
+
+    /// Translates the offset in the output to the offset in the input. The
+    /// offset that isn't inside of any mapping (i.e. it's in the whitespace
+    /// between the tokens) sticks to the end of the preceding token.
+    pub fn input_offset(&self, output_offset: usize) -> usize {
+        translate(&self.mappings, output_offset, |mapping| {
+            (&mapping.output, &mapping.input)
+        })
+    }
+
+    /// Translates the offset in the input to the offset in the output. Same as
+    /// [`SourceMap::input_offset()`], but in the opposite direction.
+    pub fn output_offset(&self, input_offset: usize) -> usize {
+        translate(&self.mappings, input_offset, |mapping| {
+            (&mapping.input, &mapping.output)
+        })
+    }
+}
+
+fn translate(
+    mappings: &[Mapping],
+    offset: usize,
+    ranges: impl Fn(&Mapping) -> (&Range<usize>, &Range<usize>),
+) -> usize {
+    let index = mappings.partition_point(|mapping| ranges(mapping).0.start <= offset);
+
+    let Some(mapping) = index.checked_sub(1).and_then(|index| mappings.get(index)) else {
+        return 0;
+    };
+
+    let (from, to) = ranges(mapping);
+
+    to.start + cmp::min(offset, from.end) - from.start
+}
@@ -0,0 +1,14 @@
+use std::ops::Range;
+
+/// Replacement of a range of bytes in the input with the new text returned
+/// from [`Decondenser::format_range()`].
+///
+/// [`Decondenser::format_range()`]: crate::Decondenser::format_range()
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextEdit {
+    /// Byte range in the input to replace
+    pub range: Range<usize>,
+
+    /// Text to put in place of the range
+    pub new_text: String,
+}
@@ -3,14 +3,29 @@ impl super::Decondenser {
     /// Display the layout using special characters in the output:
     /// - `«»` - groups with [`crate::BreakStyle::consistent()`]
     /// - `‹›` - groups with [`crate::BreakStyle::compact()`]
+    /// - `⟦⟧` - groups with [`crate::BreakStyle::table()`]
+    /// - `⟪⟫` - groups with [`crate::BreakStyle::fill()`]
     pub fn debug_layout(mut self, value: bool) -> Self {
         self.debug_layout = value;
         self
     }
 
-    /// Show indentation levels in the output using subscript number characters
+    /// Show indentation levels in the output using subscript number characters.
+    /// The start and the end of the alignment with [`crate::IndentStyle::align()`]
+    /// are shown as `ₐ` and `₋ₐ`.
     pub fn debug_indent(mut self, value: bool) -> Self {
         self.debug_indent = value;
         self
     }
+
+    /// The character that starts an escape sequence inside of the quotes.
+    /// Defaults to `\`.
+    ///
+    /// Only the escape character itself is configurable. The sequences that
+    /// follow it are the same as with `\`, and the escape character repeated
+    /// twice stands for itself.
+    pub fn escape_char(mut self, value: char) -> Self {
+        self.escape_char = value;
+        self
+    }
 }
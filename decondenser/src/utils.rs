@@ -27,3 +27,14 @@ macro_rules! scope_path {
 }
 
 pub(crate) use scope_path;
+
+/// Replaces the item with the same key in place or appends it to the end
+pub(crate) fn upsert<T>(items: &mut Vec<T>, item: T, key: impl Fn(&T) -> &str) {
+    match items
+        .iter_mut()
+        .find(|existing| key(existing) == key(&item))
+    {
+        Some(existing) => *existing = item,
+        None => items.push(item),
+    }
+}
@@ -0,0 +1,65 @@
+use std::fmt;
+
+/// Error returned from [`Decondenser::verify()`] when formatting of the
+/// already formatted output changes it.
+///
+/// [`Decondenser::verify()`]: crate::Decondenser::verify()
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IdempotencyError {
+    /// 1-based number of the first line that differs between the outputs
+    pub line: usize,
+
+    /// The line in the output of the first formatting pass including its line
+    /// terminator, or an empty string if the output has fewer lines
+    pub first: String,
+
+    /// The line in the output of the second formatting pass including its line
+    /// terminator, or an empty string if the output has fewer lines
+    pub second: String,
+}
+
+impl fmt::Display for IdempotencyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "formatting is not idempotent, the output diverges at line {}:\n\
+            first pass:  {:?}\n\
+            second pass: {:?}",
+            self.line, self.first, self.second
+        )
+    }
+}
+
+impl std::error::Error for IdempotencyError {}
+
+impl crate::Decondenser {
+    pub(crate) fn verify_impl(&self, input: &str) -> Result<String, IdempotencyError> {
+        let first = self.format(input);
+        let second = self.format(&first);
+
+        if first == second {
+            return Ok(first);
+        }
+
+        // Line terminators are kept, so that the difference in `\r` or in the
+        // trailing newline is reported at the line where it occurs
+        let mut first_lines = first.split_inclusive('\n');
+        let mut second_lines = second.split_inclusive('\n');
+
+        let mut line = 1;
+
+        loop {
+            let (first_line, second_line) = (first_lines.next(), second_lines.next());
+
+            if first_line != second_line {
+                return Err(IdempotencyError {
+                    line,
+                    first: first_line.unwrap_or_default().to_owned(),
+                    second: second_line.unwrap_or_default().to_owned(),
+                });
+            }
+
+            line += 1;
+        }
+    }
+}
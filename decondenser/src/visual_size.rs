@@ -1,3 +1,6 @@
+use crate::ansi;
+use crate::config_data::VisualSizeConfig;
+use std::any::Any;
 use std::fmt;
 use std::ops::Deref;
 use std::sync::Arc;
@@ -21,6 +24,101 @@ impl<F: Fn(&str) -> usize + Send + Sync + 'static> VisualSize for F {
     }
 }
 
+/// The default [`VisualSize`] algorithm. It counts the number of [`char`]s in
+/// the string with the exception of `\r` characters and ANSI escape sequences.
+#[derive(Debug, Clone, Copy)]
+pub struct CharCount;
+
+impl VisualSize for CharCount {
+    fn visual_size(&self, str: &str) -> usize {
+        visible_segments(str)
+            .flat_map(str::chars)
+            .filter(|&char| char != '\r')
+            .count()
+    }
+}
+
+/// Measures the number of terminal columns the string occupies.
+///
+/// The width is defined by the [Unicode Standard Annex #11](https://www.unicode.org/reports/tr11/).
+/// For example, most CJK characters and emoji take two columns. ANSI escape
+/// sequences are ignored.
+#[cfg(feature = "unicode-width")]
+#[derive(Debug, Clone, Copy)]
+pub struct UnicodeWidth;
+
+#[cfg(feature = "unicode-width")]
+impl VisualSize for UnicodeWidth {
+    fn visual_size(&self, str: &str) -> usize {
+        visible_segments(str)
+            .map(unicode_width::UnicodeWidthStr::width)
+            .sum()
+    }
+}
+
+/// Counts the number of user-perceived characters in the string.
+///
+/// These are extended grapheme clusters as defined by the [Unicode Standard
+/// Annex #29](https://www.unicode.org/reports/tr29/). For example, a letter
+/// with combining marks or an emoji made of several code points count as one.
+/// `\r` characters and ANSI escape sequences are ignored.
+#[cfg(feature = "unicode-width")]
+#[derive(Debug, Clone, Copy)]
+pub struct GraphemeCount;
+
+#[cfg(feature = "unicode-width")]
+impl VisualSize for GraphemeCount {
+    fn visual_size(&self, str: &str) -> usize {
+        use unicode_segmentation::UnicodeSegmentation;
+
+        visible_segments(str)
+            .flat_map(|segment| segment.graphemes(true))
+            .filter(|&grapheme| grapheme != "\r")
+            .count()
+    }
+}
+
+#[cfg(feature = "unicode-width")]
+fn builtin_unicode_config(any: &dyn Any) -> Option<VisualSizeConfig> {
+    if any.is::<UnicodeWidth>() {
+        return Some(VisualSizeConfig::Width);
+    }
+
+    any.is::<GraphemeCount>()
+        .then_some(VisualSizeConfig::Graphemes)
+}
+
+#[cfg(not(feature = "unicode-width"))]
+fn builtin_unicode_config(_: &dyn Any) -> Option<VisualSizeConfig> {
+    None
+}
+
+/// Splits the string into the parts between ANSI escape sequences.
+fn visible_segments(str: &str) -> impl Iterator<Item = &str> {
+    let mut rest = str;
+
+    std::iter::from_fn(move || {
+        while let Some(len) = ansi::escape_sequence_len(rest) {
+            rest = &rest[len..];
+        }
+
+        if rest.is_empty() {
+            return None;
+        }
+
+        let end = rest
+            .match_indices('\x1b')
+            .map(|(i, _)| i)
+            .find(|&i| ansi::escape_sequence_len(&rest[i..]).is_some())
+            .unwrap_or(rest.len());
+
+        let (segment, tail) = rest.split_at(end);
+        rest = tail;
+
+        Some(segment)
+    })
+}
+
 #[derive(Clone)]
 pub(crate) struct ArcVisualSize {
     inner: Arc<dyn VisualSize>,
@@ -28,26 +126,72 @@ pub(crate) struct ArcVisualSize {
     /// Store the additional info about the type of the function for debugging
     /// purposes.
     name: &'static str,
+
+    /// `None` if the algorithm isn't one of the built-in ones
+    config: Option<VisualSizeConfig>,
 }
 
 impl ArcVisualSize {
     pub(crate) fn new<T: VisualSize>(inner: T) -> Self {
+        let any: &dyn Any = &inner;
+
+        let config = if any.is::<CharCount>() {
+            Some(VisualSizeConfig::Chars)
+        } else {
+            builtin_unicode_config(any)
+        };
+
         Self {
             inner: Arc::new(inner),
             name: std::any::type_name::<T>(),
+            config,
         }
     }
 
-    pub(crate) fn measure(&self, content: &str) -> usize {
-        (self.inner).visual_size(content)
+    pub(crate) fn config(&self) -> Option<VisualSizeConfig> {
+        self.config
     }
 
-    pub(crate) fn measured_str<'s>(&self, content: &'s str) -> MeasuredStr<'s> {
+    /// Measures the size of the content as if it was printed on a single line.
+    /// The exact size of tabs depends on the column where they are printed,
+    /// so here they are assumed to take the maximum of `tab_width`.
+    pub(crate) fn measure(&self, content: &str, tab_width: usize) -> usize {
+        if !content.contains('\t') {
+            return self.inner.visual_size(content);
+        }
+
+        content
+            .split('\t')
+            .map(|segment| self.inner.visual_size(segment))
+            .sum::<usize>()
+            + content.matches('\t').count() * tab_width
+    }
+
+    pub(crate) fn measured_str<'s>(&self, content: &'s str, tab_width: usize) -> MeasuredStr<'s> {
         MeasuredStr {
-            visual_size: self.measure(content),
+            visual_size: self.measure(content, tab_width),
             content,
+            source: None,
         }
     }
+
+    /// Returns the column at which the content printed at the given `column`
+    /// ends. Tabs advance the column to the next multiple of `tab_width`.
+    pub(crate) fn advance_column(&self, column: usize, content: &str, tab_width: usize) -> usize {
+        let mut segments = content.split('\t');
+
+        let Some(first) = segments.next() else {
+            return column;
+        };
+
+        segments.fold(column + self.inner.visual_size(first), |column, segment| {
+            let tab_stop = match tab_width {
+                0 => column,
+                _ => (column / tab_width + 1) * tab_width,
+            };
+            tab_stop + self.inner.visual_size(segment)
+        })
+    }
 }
 
 impl fmt::Debug for ArcVisualSize {
@@ -61,9 +205,32 @@ pub(crate) struct MeasuredStr<'a> {
     /// The size of the string in characters.
     visual_size: usize,
     content: &'a str,
+
+    /// Offset of the content in the input if it was copied from there as is.
+    /// Used to build the [`SourceMap`](crate::SourceMap).
+    source: Option<usize>,
 }
 
-impl MeasuredStr<'_> {
+impl<'a> MeasuredStr<'a> {
+    /// Content that takes no visual space when displayed, e.g. ANSI escapes.
+    pub(crate) fn invisible(content: &'a str) -> Self {
+        Self {
+            visual_size: 0,
+            content,
+            source: None,
+        }
+    }
+
+    /// Marks the content as copied from the given offset in the input
+    pub(crate) fn with_source(mut self, offset: usize) -> Self {
+        self.source = Some(offset);
+        self
+    }
+
+    pub(crate) fn source(&self) -> Option<usize> {
+        self.source
+    }
+
     pub(crate) fn as_str(&self) -> &str {
         self.content
     }
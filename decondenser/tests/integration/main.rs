@@ -1,6 +1,9 @@
 //! Integration tests for the decondenser library.
 
-use decondenser::Decondenser;
+use decondenser::{
+    BreakStyle, Decondenser, DecondenserConfig, Group, IndentStyle, LayoutAlgorithm, Punct, Quote,
+    SourceMap, Space,
+};
 use std::borrow::Cow;
 use std::io::Write;
 use std::path::{Path, PathBuf};
@@ -42,6 +45,10 @@ fn formatting_toml() {
             decondenser = decondenser.max_line_size(max_line_size);
         }
 
+        if let Some(tab_width) = usize("tab_width") {
+            decondenser = decondenser.tab_width(tab_width);
+        }
+
         if let Some(no_break_size) = usize("no_break_size") {
             decondenser = decondenser.no_break_size(no_break_size);
         }
@@ -50,6 +57,101 @@ fn formatting_toml() {
             decondenser = decondenser.debug_layout(debug_layout);
         }
 
+        if let Some(debug_indent) = bool("debug_indent") {
+            decondenser = decondenser.debug_indent(debug_indent);
+        }
+
+        // The groups are overridden only by the tests that configure them
+        let mut parens = None;
+        let mut brackets = None;
+        let mut braces = None;
+
+        if let Some(align_after) = test.get("align_after") {
+            let align_after = Punct::new(align_after.as_str().unwrap().to_owned())
+                .leading_space(0..=1)
+                .trailing_space(1);
+
+            patch_group(&mut braces, "{", |group| group.align_after(align_after));
+        }
+
+        if let Some(max_align_padding) = usize("max_align_padding") {
+            patch_group(&mut braces, "{", |group| {
+                group.max_align_padding(max_align_padding)
+            });
+        }
+
+        if let Some(break_style) = test.get("break_style") {
+            let break_style = match break_style.as_str().unwrap() {
+                "consistent" => BreakStyle::consistent(),
+                "compact" => BreakStyle::compact(),
+                "table" => BreakStyle::table(),
+                "fill" => BreakStyle::fill(),
+                other => panic!("Unknown break_style: {other}"),
+            };
+
+            patch_group(&mut brackets, "[", |group| group.break_style(break_style));
+        }
+
+        if let Some(max_items) = usize("consistent_if_more_than") {
+            patch_group(&mut brackets, "[", |group| {
+                group.break_style(BreakStyle::consistent_if_more_than(max_items))
+            });
+        }
+
+        if let Some(braces_indent) = usize("braces_indent") {
+            patch_group(&mut braces, "{", |group| group.indent(braces_indent));
+        }
+
+        if let Some(indent_style) = test.get("indent_style") {
+            let indent_style = match indent_style.as_str().unwrap() {
+                "block" => IndentStyle::block(),
+                "align" => IndentStyle::align(),
+                other => panic!("Unknown indent_style: {other}"),
+            };
+
+            patch_group(&mut parens, "(", |group| group.indent_style(indent_style));
+        }
+
+        decondenser = decondenser.add_groups([parens, brackets, braces].into_iter().flatten());
+
+        if let Some(concat_operator) = test.get("concat_operator") {
+            let concat_operator = concat_operator.as_str().unwrap().to_owned();
+            decondenser = decondenser.quotes([
+                Quote::new("\"", "\"").concat_operator(concat_operator),
+                Quote::new("'", "'"),
+            ]);
+        }
+
+        if let Some(wrap_raw_after) = test.get("wrap_raw_after") {
+            let wrap_raw_after = wrap_raw_after.as_array().unwrap().iter();
+            decondenser = decondenser.wrap_raw_after(
+                wrap_raw_after.map(|sequence| sequence.as_str().unwrap().to_owned()),
+            );
+        }
+
+        if let Some(puncts) = test.get("puncts") {
+            let puncts = puncts.as_array().unwrap().iter();
+            decondenser = decondenser
+                .add_puncts(puncts.map(|symbol| Punct::new(symbol.as_str().unwrap().to_owned())));
+        }
+
+        if let Some(visual_size) = test.get("visual_size") {
+            decondenser = match visual_size.as_str().unwrap() {
+                "chars" => decondenser.visual_size(decondenser::CharCount),
+                "width" => decondenser.visual_size(decondenser::UnicodeWidth),
+                "graphemes" => decondenser.visual_size(decondenser::GraphemeCount),
+                other => panic!("Unknown visual_size: {other}"),
+            };
+        }
+
+        if let Some(layout_algorithm) = test.get("layout_algorithm") {
+            decondenser = decondenser.layout_algorithm(match layout_algorithm.as_str().unwrap() {
+                "oppen" => LayoutAlgorithm::oppen(),
+                "wadler" => LayoutAlgorithm::wadler(),
+                other => panic!("Unknown layout_algorithm: {other}"),
+            });
+        }
+
         if let Some(indent) = test.get("indent") {
             if let Some(indent) = indent.as_str() {
                 decondenser = decondenser.indent(indent.to_owned());
@@ -58,10 +160,101 @@ fn formatting_toml() {
             }
         }
 
-        test["output"] = decondenser.format(input).into();
+        if let Some(escape_char) = test.get("escape_char") {
+            let escape_char = escape_char.as_str().unwrap().chars().next().unwrap();
+            decondenser = decondenser.escape_char(escape_char);
+        }
+
+        // Formats only the part of the input, and applies the edit to it
+        if let Some(range) = test.get("range") {
+            let range = range.as_array().unwrap();
+            let [start, end] = [0, 1]
+                .map(|i| usize::try_from(range.get(i).unwrap().as_integer().unwrap()).unwrap());
+
+            let edit = decondenser.format_range(input, start..end);
+
+            let mut output = input.to_owned();
+            output.replace_range(edit.range, &edit.new_text);
+
+            test["output"] = output.into();
+            return;
+        }
+
+        // Snapshots the error message if the input is rejected
+        if bool("strict") == Some(true) {
+            let output = decondenser
+                .strict(true)
+                .try_format(input)
+                .unwrap_or_else(|err| err.to_string());
+
+            test["output"] = output.into();
+            return;
+        }
+
+        let (output, source_map) = decondenser.format_with_map(input);
+        assert_source_map(input, &output, &source_map);
+        assert_content_preserved(&decondenser, input, &output);
+
+        test["output"] = output.into();
     });
 }
 
+/// Every mapped range of the output must be an exact copy of the input range,
+/// and the mappings must go in the same order in both of them.
+fn assert_source_map(input: &str, output: &str, source_map: &SourceMap) {
+    let mappings = source_map.mappings();
+
+    for mapping in mappings {
+        assert_eq!(
+            output[mapping.output.clone()],
+            input[mapping.input.clone()],
+            "{mapping:?}"
+        );
+
+        assert_eq!(
+            source_map.input_offset(mapping.output.start),
+            mapping.input.start
+        );
+        assert_eq!(
+            source_map.output_offset(mapping.input.start),
+            mapping.output.start
+        );
+    }
+
+    for pair in mappings.windows(2) {
+        assert!(pair[0].output.end <= pair[1].output.start, "{pair:?}");
+        assert!(pair[0].input.end <= pair[1].input.start, "{pair:?}");
+    }
+}
+
+/// The formatting must change only the whitespace of the input
+fn assert_content_preserved(decondenser: &Decondenser, input: &str, output: &str) {
+    let checked = decondenser
+        .try_format(input)
+        .unwrap_or_else(|err| panic!("{err}\ninput:\n{input}"));
+
+    assert_eq!(checked, output);
+}
+
+/// Applies the patch to the group, which is initially the same as in
+/// [`Decondenser::generic()`]
+fn patch_group(group: &mut Option<Group>, opening: &str, patch: impl FnOnce(Group) -> Group) {
+    let base = group.take().unwrap_or_else(|| generic_group(opening));
+    *group = Some(patch(base));
+}
+
+fn generic_group(opening: &str) -> Group {
+    let config = Decondenser::generic()
+        .to_config()
+        .groups
+        .unwrap()
+        .into_iter()
+        .find(|group| group.opening.symbol == opening)
+        .unwrap();
+
+    Group::from_config(config)
+}
+
 #[test]
 fn formatting_dir() {
     let tests = std::fs::read_dir(tests_dir().join("formatting")).unwrap();
@@ -79,7 +272,9 @@ fn formatting_dir() {
         let decondenser = Decondenser::generic();
 
         let input = std::fs::read_to_string(&test_path).unwrap();
-        let output = decondenser.format(&input);
+        let (output, source_map) = decondenser.format_with_map(&input);
+        assert_source_map(&input, &output, &source_map);
+        assert_content_preserved(&decondenser, &input, &output);
 
         test_name_parts.insert(test_name_parts.len().saturating_sub(1), "out");
         let out_path = test_path.with_file_name(test_name_parts.join("."));
@@ -88,6 +283,219 @@ fn formatting_dir() {
     }
 }
 
+/// Formatting of the already formatted output must not change it for any
+/// combination of the line size limits and layout algorithms.
+#[test]
+fn idempotency_dir() {
+    let tests = std::fs::read_dir(tests_dir().join("formatting")).unwrap();
+
+    for test in tests {
+        let test_path = test.unwrap().path();
+
+        if test_path.to_string_lossy().contains(".out.") {
+            continue;
+        }
+
+        let input = std::fs::read_to_string(&test_path).unwrap();
+
+        for layout_algorithm in [LayoutAlgorithm::oppen(), LayoutAlgorithm::wadler()] {
+            for max_line_size in [10, 20, 40, 80, 120] {
+                let no_break_sizes = [None, Some(0), Some(5), Some(max_line_size / 4)];
+
+                for no_break_size in no_break_sizes {
+                    let mut decondenser = Decondenser::generic()
+                        .max_line_size(max_line_size)
+                        .layout_algorithm(layout_algorithm.clone());
+
+                    if let Some(no_break_size) = no_break_size {
+                        decondenser = decondenser.no_break_size(no_break_size);
+                    }
+
+                    if let Err(err) = decondenser.verify(&input) {
+                        panic!(
+                            "{}, max_line_size: {max_line_size}, \
+                            no_break_size: {no_break_size:?}, \
+                            layout_algorithm: {layout_algorithm:?}\n{err}",
+                            test_path.display(),
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Same as [`idempotency_dir`], but for the long quoted content and raw runs
+/// that are split into several lines
+#[test]
+fn idempotency_wrapping() {
+    const INPUTS: &[&str] = &[
+        "abcd https://example.com/x std::::collections::HashMap",
+        "Request { url: https://example.com/api/v1/users/12345/profile/settings, \
+        path: std::collections::hash_map::HashMap }",
+        r#"Error { message: "the quick brown fox jumps over the lazy dog", short: "ok" }"#,
+        r#"["lorem ipsum dolor sit amet\nconsectetur adipiscing elit", "ok"]"#,
+    ];
+
+    for input in INPUTS {
+        for concat_operator in ["+", "", "\\"] {
+            for layout_algorithm in [LayoutAlgorithm::oppen(), LayoutAlgorithm::wadler()] {
+                for max_line_size in [10, 12, 20, 40, 80] {
+                    let decondenser = Decondenser::generic()
+                        .max_line_size(max_line_size)
+                        .layout_algorithm(layout_algorithm.clone())
+                        .wrap_raw_after(["/", "::"])
+                        .quotes([Quote::new("\"", "\"").concat_operator(concat_operator)]);
+
+                    if let Err(err) = decondenser.verify(input) {
+                        panic!(
+                            "{input:?}, max_line_size: {max_line_size}, \
+                            concat_operator: {concat_operator:?}, \
+                            layout_algorithm: {layout_algorithm:?}\n{err}",
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Same as [`idempotency_dir`], but for the inputs generated from a vocabulary
+/// of tokens. The generator is seeded, so the failures are reproducible. It
+/// produces a lot of malformed input such as unclosed groups and quotes.
+#[test]
+fn idempotency_generated() {
+    const VOCABULARY: &[&str] = &[
+        "(", ")", "[", "]", "{", "}", "<<", ">>", ",", ";", ":", "=>", "\"", "'", "\\", " ", "  ",
+        "\t", "\n", " \n ", "\n\n", "x", "yy", "123", "a::b", "zzzzzzzz",
+    ];
+
+    let mut rng = XorShift(0x9e37_79b9_7f4a_7c15);
+
+    for _ in 0..2000 {
+        let len = rng.below(40);
+        let input: String = (0..len)
+            .map(|_| VOCABULARY[rng.below(VOCABULARY.len())])
+            .collect();
+
+        let max_line_size = 1 + rng.below(60);
+        let no_break_size = [None, Some(0), Some(5)][rng.below(3)];
+
+        for layout_algorithm in [LayoutAlgorithm::oppen(), LayoutAlgorithm::wadler()] {
+            let mut decondenser = Decondenser::generic()
+                .max_line_size(max_line_size)
+                .layout_algorithm(layout_algorithm.clone());
+
+            if let Some(no_break_size) = no_break_size {
+                decondenser = decondenser.no_break_size(no_break_size);
+            }
+
+            if let Err(err) = decondenser.verify(&input) {
+                panic!(
+                    "{input:?}, max_line_size: {max_line_size}, \
+                    no_break_size: {no_break_size:?}, \
+                    layout_algorithm: {layout_algorithm:?}\n{err}",
+                );
+            }
+        }
+    }
+}
+
+/// Minimal deterministic pseudo-random number generator
+struct XorShift(u64);
+
+impl XorShift {
+    fn below(&mut self, max: usize) -> usize {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        usize::try_from(self.0 % u64::try_from(max).unwrap()).unwrap()
+    }
+}
+
+/// The config exported from a [`Decondenser`] must produce the same
+/// [`Decondenser`], and survive the serialization
+#[test]
+fn config_roundtrip() {
+    let custom = Decondenser::empty()
+        .indent("\t")
+        .max_line_size(60)
+        .no_break_size(10)
+        .visual_size(decondenser::UnicodeWidth)
+        .layout_algorithm(LayoutAlgorithm::wadler())
+        .strict(true)
+        .groups([
+            generic_group("(")
+                .break_style(BreakStyle::consistent_if_more_than(3))
+                .indent_style(IndentStyle::align()),
+            generic_group("{")
+                .indent(2)
+                .align_after(Punct::new(":").trailing_space(1))
+                .max_align_padding(8),
+        ])
+        .puncts([Punct::new(",").trailing_space(Space::new().breakable(true))])
+        .quotes([
+            Quote::new("\"", "\"").concat_operator("+"),
+            Quote::new("'", "'").concat_operator(""),
+        ])
+        .wrap_raw_after(["/"])
+        .escape_char('^')
+        .debug_indent(true);
+
+    for decondenser in [Decondenser::empty(), Decondenser::generic(), custom] {
+        let config = decondenser.to_config();
+
+        assert_eq!(Decondenser::from_config(config.clone()).to_config(), config);
+
+        let json = serde_json::to_string(&config).unwrap();
+        let deserialized = serde_json::from_str::<DecondenserConfig>(&json).unwrap();
+
+        assert_eq!(deserialized, config, "{json}");
+
+        let input = "foo(aaa, { bbb: 'ccc', ddd: \"eee\" }, [fff, ggg], hhh/iii)";
+
+        assert_eq!(
+            Decondenser::from_config(config).format(input),
+            decondenser.format(input),
+        );
+    }
+}
+
+#[test]
+fn diagnostics() {
+    let input = r#"foo(a]], "b\qc", { d: "e\x"#;
+
+    let diagnostics = Decondenser::generic()
+        .diagnostics(input)
+        .into_iter()
+        .map(|diagnostic| {
+            let range = diagnostic.range().unwrap();
+            (diagnostic.to_string(), &input[range])
+        })
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        diagnostics,
+        [
+            ("unclosed group `(` at offset 3".to_owned(), "("),
+            ("unmatched closing `]` at offset 5".to_owned(), "]"),
+            ("unmatched closing `]` at offset 6".to_owned(), "]"),
+            ("invalid escape `\\q` at offset 11".to_owned(), "\\q"),
+            ("unclosed group `{` at offset 17".to_owned(), "{"),
+            ("unclosed quote `\"` at offset 22".to_owned(), "\""),
+            ("invalid escape `\\x` at offset 24".to_owned(), "\\x"),
+        ]
+    );
+
+    // The first diagnostic is the one reported by the strict mode
+    let err = Decondenser::generic()
+        .strict(true)
+        .try_format(input)
+        .unwrap_err();
+
+    assert_eq!(err.to_string(), diagnostics[0].0);
+}
+
 #[test]
 fn unescaping_toml() {
     Snapshot::new("unescaping.toml").update(|test| {
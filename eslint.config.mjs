@@ -15,6 +15,7 @@ export default tseslint.config(
                     allowDefaultProject: [
                         "eslint.config.mjs",
                         "decondenser-vscode/esbuild.mjs",
+                        "decondenser-ts/host.mjs",
                     ],
                     defaultProject: "./tsconfig.base.json",
                 },